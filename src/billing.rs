@@ -0,0 +1,400 @@
+use crate::iof::{CCard, Competitor, CompetitorStatus, EntryFee, ValueOperator};
+use crate::totals::Totals;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Serialize)]
+pub struct Stage {
+    pub number: usize,
+    pub name: Option<String>,
+    pub race_date: NaiveDate,
+    // Usually the class of the event line, but runners may change class between stages.
+    pub class_name: String,
+    pub normal_fee: f64,
+    pub late_fee: f64,
+    pub status: CompetitorStatus,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum ExtraKind {
+    CardRental,
+    Service,
+}
+
+impl fmt::Display for ExtraKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExtraKind::CardRental => write!(f, "Card rental"),
+            ExtraKind::Service => write!(f, "Service"),
+        }
+    }
+}
+
+// Billed to the member in full, regardless of how the club shares entry fees with its members.
+#[derive(Debug, Serialize)]
+pub struct Extra {
+    pub kind: ExtraKind,
+    pub description: String,
+    pub amount: f64,
+}
+
+// What kind of event a line is for. Lines of all kinds follow the same billing rules.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum Category {
+    Eventor,
+    // Timed with MeOS or OLA, but not in Eventor.
+    Local,
+    // Outside any Eventor, from the external events ledger.
+    External,
+}
+
+// Where a billable line comes from, so that it can be verified and reproduced later. The cache
+// keys identify the responses used by the SHA-256 of their content.
+#[derive(Debug, Serialize)]
+pub struct Provenance {
+    pub event_id: u64,
+    pub event_url: Option<String>,
+    pub event_race_ids: Vec<u64>,
+    pub event_class_id: u64,
+    pub entry_id: Option<u64>,
+    pub fee_ids: Vec<u64>,
+    pub cache_keys: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BillableEvent {
+    pub category: Category,
+    pub race_date: NaiveDate,
+    pub event_name: String,
+    pub class_name: String,
+    // For multi-day events these are the fees billed once for the whole event. Fees that
+    // the organiser has tied to a specific stage are found in the stage instead.
+    pub normal_fee: f64,
+    pub late_fee: f64,
+    pub status: CompetitorStatus,
+    // Empty unless this is a multi-day event.
+    pub stages: Vec<Stage>,
+    // Only set for relays.
+    pub leg: Option<u64>,
+    pub extras: Vec<Extra>,
+    pub provenance: Provenance,
+    // Notes on changes made to the line after the fees were computed, such as manual corrections.
+    pub overrides: Vec<String>,
+    // Set when the member has already paid for the line, e.g. on site. The fees are kept, but
+    // are not due.
+    pub paid: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Person {
+    pub person: Competitor,
+    pub billable: Vec<BillableEvent>,
+    // Whether the person is in the club's roster, if it was checked.
+    pub member: Option<bool>,
+    // Set once all lines are billed.
+    pub totals: Totals,
+}
+
+// How the fee for a relay team is divided between the runners. The fee is divided by the number
+// of runners in the team, also when some of them are from other clubs, as their shares are for
+// their own clubs to pay. The shares of runners with a waived status are paid by the club, and
+// are not moved onto the other runners.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TeamFeeSplit {
+    // Equal share for each leg runner.
+    Equal,
+    // The club pays, nothing is billed to the runners.
+    Club,
+    // The club's first runner, by leg, pays the shares of all the club's runners. If that runner
+    // has a waived status, the next of the club's runners pays instead.
+    Captain,
+}
+
+impl FromStr for TeamFeeSplit {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "equal" => Ok(TeamFeeSplit::Equal),
+            "club" => Ok(TeamFeeSplit::Club),
+            "captain" => Ok(TeamFeeSplit::Captain),
+            _ => Err("Unrecognized team fee split, should be one of equal, club or captain."),
+        }
+    }
+}
+
+impl TeamFeeSplit {
+    // The fees billed to each of the club's runners in a team of `team_size` runners, given the
+    // statuses of the club's runners in leg order.
+    pub fn shares(
+        &self,
+        team_fee: (f64, f64),
+        team_size: usize,
+        statuses: &[CompetitorStatus],
+        waived: &[CompetitorStatus],
+    ) -> Vec<(f64, f64)> {
+        let share = (team_fee.0 / team_size as f64, team_fee.1 / team_size as f64);
+        let payer = statuses.iter().position(|status| !waived.contains(status));
+        statuses
+            .iter()
+            .enumerate()
+            .map(|(index, status)| match self {
+                TeamFeeSplit::Equal if !waived.contains(status) => share,
+                TeamFeeSplit::Captain if Some(index) == payer => {
+                    (share.0 * statuses.len() as f64, share.1 * statuses.len() as f64)
+                }
+                _ => (0f64, 0f64),
+            })
+            .collect()
+    }
+}
+
+// What to do with lines for events that the organiser has cancelled.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CancelledPolicy {
+    // Bill as usual, e.g. if the organiser does not refund the fees.
+    Bill,
+    // Keep the lines, but with zero fees.
+    Waive,
+    // Leave the event out altogether.
+    Skip,
+}
+
+impl FromStr for CancelledPolicy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bill" => Ok(CancelledPolicy::Bill),
+            "waive" => Ok(CancelledPolicy::Waive),
+            "skip" => Ok(CancelledPolicy::Skip),
+            _ => Err("Unrecognized policy for cancelled events, should be one of bill, waive or skip."),
+        }
+    }
+}
+
+// Finished races are left blank. Anything else is worth a closer look.
+pub fn status_label(status: &CompetitorStatus) -> String {
+    match status {
+        CompetitorStatus::OK | CompetitorStatus::Finished => "".to_string(),
+        status => status.to_string(),
+    }
+}
+
+impl BillableEvent {
+    pub fn status_label(&self) -> String {
+        status_label(&self.status)
+    }
+
+    pub fn display_name(&self) -> String {
+        match self.leg {
+            Some(leg) => format!("{}, leg {}", self.event_name, leg),
+            None => self.event_name.clone(),
+        }
+    }
+
+    // Nothing is billed for the line, including extras. The line itself is kept.
+    pub fn waive(&mut self) {
+        self.normal_fee = 0f64;
+        self.late_fee = 0f64;
+        for stage in self.stages.iter_mut() {
+            stage.normal_fee = 0f64;
+            stage.late_fee = 0f64;
+        }
+        for extra in self.extras.iter_mut() {
+            extra.amount = 0f64;
+        }
+    }
+
+    // The status shown for the event as a whole. For a multi-day event where the competitor
+    // started at least once, the first remarkable status among the started stages is used.
+    pub fn summary_status(stages: &[Stage]) -> CompetitorStatus {
+        if stages.iter().all(|stage| stage.status == stages[0].status) {
+            stages[0].status
+        } else if !stages.iter().any(|stage| stage.status.started()) {
+            CompetitorStatus::DidNotStart
+        } else {
+            stages
+                .iter()
+                .filter(|stage| stage.status.started())
+                .map(|stage| stage.status)
+                .find(|status| *status != CompetitorStatus::OK && *status != CompetitorStatus::Finished)
+                .unwrap_or(CompetitorStatus::OK)
+        }
+    }
+}
+
+// Splits the fees for an entry into the part billed once for the event and the parts that
+// belong to each stage. Returns the event fees and the stage fees in the same order as `stages`.
+// Fees for stages that the club does not bill (according to `waived`) are zeroed, and the event
+// fees are only zeroed if every stage is waived.
+//
+// Percent fees are late fees on the fixed fees billed: those for the whole event on all of them,
+// and those tied to a stage on the fixed fees of that stage. Events with a single race are billed
+// as a whole, even if the organiser has tied fees to the race.
+pub fn split_fees(
+    fee_ids: &[u64],
+    entry_fees: &[EntryFee],
+    stages: &[(u64, CompetitorStatus)],
+    waived: &[CompetitorStatus],
+    multi_day: bool,
+) -> ((f64, f64), Vec<(f64, f64)>) {
+    let all_waived = stages.iter().all(|(_, status)| waived.contains(status));
+    if !multi_day {
+        let paid = if all_waived { (0f64, 0f64) } else { EntryFee::paid_fees_from_fee_ids(fee_ids, entry_fees) };
+        return (paid, stages.iter().map(|_| (0f64, 0f64)).collect());
+    }
+
+    let fees: Vec<&EntryFee> = fee_ids.iter().filter_map(|fee_id| EntryFee::find(fee_id, entry_fees)).collect();
+    // The fixed fees and the percentages for the event, or for a stage.
+    let group = |event_race_id: Option<u64>| -> (f64, f64) {
+        fees.iter()
+            .filter(|fee| fee.event_race_id == event_race_id)
+            .fold((0f64, 0f64), |acc, fee| match fee.operator {
+                ValueOperator::Fixed => (acc.0 + fee.amount, acc.1),
+                ValueOperator::Percent => (acc.0, acc.1 + fee.amount),
+            })
+    };
+
+    let stage_fees: Vec<(f64, f64)> = stages
+        .iter()
+        .map(|(event_race_id, status)| {
+            if waived.contains(status) {
+                return (0f64, 0f64);
+            }
+            let (fixed, percent) = group(Some(*event_race_id));
+            (fixed, fixed * percent / 100f64)
+        })
+        .collect();
+
+    let event_fees = if all_waived {
+        (0f64, 0f64)
+    } else {
+        let (fixed, percent) = group(None);
+        let base = fixed + stage_fees.iter().map(|stage| stage.0).sum::<f64>();
+        (fixed, base * percent / 100f64)
+    };
+
+    (event_fees, stage_fees)
+}
+
+// Takes out the card rental fees from the fees for an entry, since those are billed separately.
+// Returns the remaining fee ids and the rental items.
+pub fn separate_card_rental(
+    fee_ids: &[u64],
+    entry_fees: &[EntryFee],
+    card: Option<&CCard>,
+) -> (Vec<u64>, Vec<Extra>) {
+    let (rental_fee_ids, fee_ids): (Vec<u64>, Vec<u64>) = fee_ids
+        .iter()
+        .partition(|fee_id| EntryFee::find(fee_id, entry_fees).is_some_and(|fee| fee.is_card_rental()));
+
+    let extras = rental_fee_ids
+        .iter()
+        .filter_map(|fee_id| EntryFee::find(fee_id, entry_fees))
+        .map(|fee| {
+            // Worth pointing out, since the member may dispute the charge.
+            let description = match card {
+                Some(card) => format!("{} (own card {} given)", fee.name, card.id),
+                None => fee.name.clone(),
+            };
+            Extra {
+                kind: ExtraKind::CardRental,
+                description,
+                amount: EntryFee::paid_fees_from_fee_ids(&[fee.id], entry_fees).0,
+            }
+        })
+        .collect();
+
+    (fee_ids, extras)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fee(id: u64, amount: f64, operator: ValueOperator, event_race_id: Option<u64>) -> EntryFee {
+        EntryFee {
+            id,
+            name: format!("Fee {}", id),
+            amount,
+            operator,
+            from_year_of_birth: None,
+            to_year_of_birth: None,
+            event_race_id,
+            card_rental: false,
+        }
+    }
+
+    fn fees() -> Vec<EntryFee> {
+        vec![
+            fee(1, 100.0, ValueOperator::Fixed, None),
+            fee(2, 50.0, ValueOperator::Fixed, Some(10)),
+            fee(3, 50.0, ValueOperator::Fixed, Some(20)),
+            fee(4, 50.0, ValueOperator::Percent, None),
+            fee(5, 100.0, ValueOperator::Percent, Some(20)),
+        ]
+    }
+
+    const OK: CompetitorStatus = CompetitorStatus::OK;
+    const DNS: CompetitorStatus = CompetitorStatus::DidNotStart;
+
+    #[test]
+    fn event_percent_fee_applies_to_stage_fees() {
+        let (event, stages) = split_fees(&[1, 2, 3, 4], &fees(), &[(10, OK), (20, OK)], &[], true);
+        assert_eq!(event, (100.0, 100.0));
+        assert_eq!(stages, vec![(50.0, 0.0), (50.0, 0.0)]);
+    }
+
+    #[test]
+    fn stage_percent_fee_applies_to_its_stage() {
+        let (event, stages) = split_fees(&[1, 2, 3, 5], &fees(), &[(10, OK), (20, OK)], &[], true);
+        assert_eq!(event, (100.0, 0.0));
+        assert_eq!(stages, vec![(50.0, 0.0), (50.0, 50.0)]);
+    }
+
+    #[test]
+    fn waived_stage_is_left_out_of_the_percent_base() {
+        let (event, stages) = split_fees(&[1, 2, 3, 4], &fees(), &[(10, OK), (20, DNS)], &[DNS], true);
+        assert_eq!(event, (100.0, 75.0));
+        assert_eq!(stages, vec![(50.0, 0.0), (0.0, 0.0)]);
+    }
+
+    #[test]
+    fn all_stages_waived() {
+        let (event, stages) = split_fees(&[1, 2, 3, 4], &fees(), &[(10, DNS), (20, DNS)], &[DNS], true);
+        assert_eq!(event, (0.0, 0.0));
+        assert_eq!(stages, vec![(0.0, 0.0), (0.0, 0.0)]);
+    }
+
+    #[test]
+    fn equal_team_split_leaves_out_other_clubs_and_waived_runners() {
+        // Two of the club's runners in a team of three, one of whom did not start.
+        let shares = TeamFeeSplit::Equal.shares((300.0, 60.0), 3, &[OK, DNS], &[DNS]);
+        assert_eq!(shares, vec![(100.0, 20.0), (0.0, 0.0)]);
+    }
+
+    #[test]
+    fn captain_pays_for_the_club_runners() {
+        let shares = TeamFeeSplit::Captain.shares((300.0, 0.0), 3, &[OK, OK], &[DNS]);
+        assert_eq!(shares, vec![(200.0, 0.0), (0.0, 0.0)]);
+    }
+
+    #[test]
+    fn waived_captain_passes_the_fee_on() {
+        let shares = TeamFeeSplit::Captain.shares((300.0, 0.0), 3, &[DNS, OK], &[DNS]);
+        assert_eq!(shares, vec![(0.0, 0.0), (200.0, 0.0)]);
+        let shares = TeamFeeSplit::Captain.shares((300.0, 0.0), 3, &[DNS, DNS], &[DNS]);
+        assert_eq!(shares, vec![(0.0, 0.0), (0.0, 0.0)]);
+    }
+
+    #[test]
+    fn single_race_is_billed_as_a_whole() {
+        // The organiser has tied the fixed fee to the race, but the late fee is for the event.
+        let (event, stages) = split_fees(&[2, 4], &fees(), &[(10, OK)], &[], false);
+        assert_eq!(event, (50.0, 25.0));
+        assert_eq!(stages, vec![(0.0, 0.0)]);
+    }
+}
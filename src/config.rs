@@ -0,0 +1,56 @@
+use crate::dates::Season;
+use crate::filter::Rule;
+use crate::invoice::InvoiceSettings;
+use crate::local_events::LocalEventDefinition;
+use crate::totals::SharePolicy;
+use serde::Deserialize;
+use std::fs;
+
+// Settings kept by the club, so that they do not have to be given on the command line each run.
+// Options given on the command line take precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub organisation_id: Option<u64>,
+    pub cache: Option<String>,
+    #[serde(default)]
+    pub waive: Vec<String>,
+    pub team_fees: Option<String>,
+    pub cancelled: Option<String>,
+    pub iof_version: Option<String>,
+    #[serde(default)]
+    pub services: bool,
+    // The names of the fees for card rental, when guessing from the name does not work for the club.
+    #[serde(default)]
+    pub card_rental_fees: Vec<String>,
+    #[serde(default)]
+    pub roster: bool,
+    pub overrides: Option<String>,
+    pub ledger: Option<String>,
+    pub aliases: Option<String>,
+    pub payers: Option<String>,
+    pub share: Option<SharePolicy>,
+    pub format: Option<String>,
+    pub invoice: Option<InvoiceSettings>,
+    #[serde(default)]
+    pub include: Vec<Rule>,
+    #[serde(default)]
+    pub exclude: Vec<Rule>,
+    #[serde(default)]
+    pub season: Vec<Season>,
+    #[serde(default)]
+    pub local_event: Vec<LocalEventDefinition>,
+}
+
+impl Config {
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read config file {}: {}", path, e))?;
+        let config: Config =
+            toml::from_str(&contents).map_err(|e| format!("Unable to parse config file {}: {}", path, e))?;
+        for season in config.season.iter() {
+            season.validate().map_err(|e| format!("Invalid config file {}: {}", path, e))?;
+        }
+        Ok(config)
+    }
+}
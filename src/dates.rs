@@ -0,0 +1,288 @@
+use crate::iof::EVENTOR_TIME_ZONE;
+use chrono::{Datelike, Days, Months, NaiveDate, Utc};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+// The file in the cache folder where the last day billed is kept, for "since last billing".
+const LAST_BILLING_FILE: &str = "last-billing.txt";
+
+// A season that the club bills by, given by its first and last day as MM-DD. A season that ends
+// before it starts, such as a winter season, ends the following year.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Season {
+    name: String,
+    from: String,
+    to: String,
+}
+
+// The days from `from` to `to`, both included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateRange {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+// Interprets date range expressions relative to today.
+pub struct Calendar {
+    today: NaiveDate,
+    seasons: Vec<Season>,
+    last_billing: Option<NaiveDate>,
+}
+
+pub fn today() -> NaiveDate {
+    Utc::now().with_timezone(&EVENTOR_TIME_ZONE).date_naive()
+}
+
+fn month(year: i32, month: u32) -> Option<DateRange> {
+    let from = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let to = from.checked_add_months(Months::new(1))?.checked_sub_days(Days::new(1))?;
+    Some(DateRange { from, to })
+}
+
+fn quarter(year: i32, quarter: u32) -> Option<DateRange> {
+    if !(1..=4).contains(&quarter) {
+        return None;
+    }
+    Some(DateRange {
+        from: month(year, quarter * 3 - 2)?.from,
+        to: month(year, quarter * 3)?.to,
+    })
+}
+
+fn year(year: i32) -> Option<DateRange> {
+    Some(DateRange {
+        from: NaiveDate::from_ymd_opt(year, 1, 1)?,
+        to: NaiveDate::from_ymd_opt(year, 12, 31)?,
+    })
+}
+
+fn day_of_year(year: i32, month_and_day: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-{}", year, month_and_day), "%Y-%m-%d").ok()
+}
+
+impl Season {
+    // Spring and autumn halves of the year, unless the club config says otherwise.
+    fn defaults() -> Vec<Season> {
+        vec![
+            Season { name: "spring".to_string(), from: "01-01".to_string(), to: "06-30".to_string() },
+            Season { name: "autumn".to_string(), from: "07-01".to_string(), to: "12-31".to_string() },
+        ]
+    }
+
+    // Both days must be MM-DD and exist every year, so February 29 is not allowed.
+    pub fn validate(&self) -> Result<(), String> {
+        for day in [&self.from, &self.to] {
+            let well_formed = day.len() == 5 && day.as_bytes()[2] == b'-';
+            if !well_formed || day_of_year(2001, day).is_none() {
+                return Err(format!("Season '{}' has the day '{}', which is not a day of every year as MM-DD.", self.name, day));
+            }
+        }
+        Ok(())
+    }
+
+    fn in_year(&self, year: i32) -> Option<DateRange> {
+        let from = day_of_year(year, &self.from)?;
+        let mut to = day_of_year(year, &self.to)?;
+        if to < from {
+            to = day_of_year(year + 1, &self.to)?;
+        }
+        Some(DateRange { from, to })
+    }
+}
+
+impl Calendar {
+    pub fn new(seasons: Vec<Season>, cache_folder: &str) -> Calendar {
+        let mut all_seasons = seasons;
+        for season in Season::defaults() {
+            if !all_seasons.iter().any(|s| s.name == season.name) {
+                all_seasons.push(season);
+            }
+        }
+        Calendar { today: today(), seasons: all_seasons, last_billing: last_billing(cache_folder) }
+    }
+
+    // A range given by a single expression:
+    //   2022, 2022-Q3, 2022-05 or 2022-05-01 for a year, quarter, month or day,
+    //   this/last year, quarter or month, e.g. "last month",
+    //   a season with or without a year, e.g. "spring 2022" or "autumn" for this year's,
+    //   "since last billing", from the day after the last range billed up to today.
+    // Words may be separated by spaces, dashes or underscores, and case is ignored.
+    pub fn range(&self, expression: &str) -> Result<DateRange, String> {
+        let expression = expression.trim().to_lowercase();
+        let invalid = || format!("Invalid date range '{}'.", expression);
+
+        if let Ok(date) = NaiveDate::parse_from_str(&expression, "%Y-%m-%d") {
+            return Ok(DateRange { from: date, to: date });
+        }
+        if let Some((y, rest)) = expression.split_once('-') {
+            if let Ok(y) = y.parse::<i32>() {
+                let range = match rest.strip_prefix('q') {
+                    Some(q) => q.parse::<u32>().ok().and_then(|q| quarter(y, q)),
+                    None => rest.parse::<u32>().ok().and_then(|m| month(y, m)),
+                };
+                return range.ok_or_else(invalid);
+            }
+        }
+        if let Ok(y) = expression.parse::<i32>() {
+            return year(y).ok_or_else(invalid);
+        }
+
+        let words: Vec<&str> = expression.split([' ', '-', '_']).filter(|w| !w.is_empty()).collect();
+        let this_year = self.today.year();
+        let this_month = self.today.month();
+        let this_quarter = (this_month - 1) / 3 + 1;
+        let range = match words.as_slice() {
+            ["this", "year"] => year(this_year),
+            ["last", "year"] => year(this_year - 1),
+            ["this", "quarter"] => quarter(this_year, this_quarter),
+            ["last", "quarter"] if this_quarter == 1 => quarter(this_year - 1, 4),
+            ["last", "quarter"] => quarter(this_year, this_quarter - 1),
+            ["this", "month"] => month(this_year, this_month),
+            ["last", "month"] if this_month == 1 => month(this_year - 1, 12),
+            ["last", "month"] => month(this_year, this_month - 1),
+            ["since", "last", "billing"] => {
+                let last_billing = self
+                    .last_billing
+                    .ok_or("Nothing has been billed yet, so there is no last billing to start from.")?;
+                let from = last_billing.succ_opt().ok_or_else(invalid)?;
+                if from > self.today {
+                    return Err(format!("Everything up to {} has already been billed.", last_billing));
+                }
+                Some(DateRange { from, to: self.today })
+            }
+            [name] => self.season(name)?.in_year(this_year),
+            [name, y] => {
+                let y = y.parse::<i32>().map_err(|_| invalid())?;
+                self.season(name)?.in_year(y)
+            }
+            _ => None,
+        };
+        range.ok_or_else(invalid)
+    }
+
+    // A range from the start of one expression to the end of another, e.g. 2022-01-01 2022-06-30.
+    pub fn between(&self, from: &str, to: &str) -> Result<DateRange, String> {
+        let range = DateRange { from: self.range(from)?.from, to: self.range(to)?.to };
+        if range.to < range.from {
+            return Err(format!("The range ends ({}) before it starts ({}).", range.to, range.from));
+        }
+        Ok(range)
+    }
+
+    fn season(&self, name: &str) -> Result<&Season, String> {
+        self.seasons
+            .iter()
+            .find(|season| season.name.to_lowercase() == name)
+            .ok_or_else(|| format!("Unrecognized date range or season '{}'.", name))
+    }
+}
+
+fn last_billing(cache_folder: &str) -> Option<NaiveDate> {
+    let text = fs::read_to_string(Path::new(cache_folder).join(LAST_BILLING_FILE)).ok()?;
+    NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d").ok()
+}
+
+// Remembers the end of a range billed, unless it lies in the future or an earlier billing went further.
+pub fn record_billing(cache_folder: &str, range: &DateRange) {
+    let billed_to = range.to.min(today());
+    if last_billing(cache_folder).is_some_and(|last| last >= billed_to) {
+        return;
+    }
+    if fs::write(Path::new(cache_folder).join(LAST_BILLING_FILE), billed_to.to_string()).is_err() {
+        eprintln!("Unable to save the date of the last billing.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn range(from: NaiveDate, to: NaiveDate) -> DateRange {
+        DateRange { from, to }
+    }
+
+    fn season(name: &str, from: &str, to: &str) -> Season {
+        Season { name: name.to_string(), from: from.to_string(), to: to.to_string() }
+    }
+
+    fn calendar(today: NaiveDate, last_billing: Option<NaiveDate>) -> Calendar {
+        let mut seasons = vec![season("winter", "11-01", "02-28")];
+        seasons.extend(Season::defaults());
+        Calendar { today, seasons, last_billing }
+    }
+
+    #[test]
+    fn test_range_forms() {
+        let calendar = calendar(date(2023, 2, 15), Some(date(2023, 1, 31)));
+        assert_eq!(calendar.range("2022"), Ok(range(date(2022, 1, 1), date(2022, 12, 31))));
+        assert_eq!(calendar.range("2022-Q3"), Ok(range(date(2022, 7, 1), date(2022, 9, 30))));
+        assert_eq!(calendar.range("2024-02"), Ok(range(date(2024, 2, 1), date(2024, 2, 29))));
+        assert_eq!(calendar.range("2022-05-01"), Ok(range(date(2022, 5, 1), date(2022, 5, 1))));
+        assert_eq!(calendar.range("This Year"), Ok(range(date(2023, 1, 1), date(2023, 12, 31))));
+        assert_eq!(calendar.range("last_month"), Ok(range(date(2023, 1, 1), date(2023, 1, 31))));
+        assert_eq!(calendar.range("last quarter"), Ok(range(date(2022, 10, 1), date(2022, 12, 31))));
+        assert_eq!(calendar.range("spring 2022"), Ok(range(date(2022, 1, 1), date(2022, 6, 30))));
+        assert_eq!(calendar.range("autumn"), Ok(range(date(2023, 7, 1), date(2023, 12, 31))));
+        assert_eq!(calendar.range("since last billing"), Ok(range(date(2023, 2, 1), date(2023, 2, 15))));
+        assert_eq!(calendar.between("2022-11", "2023-Q1"), Ok(range(date(2022, 11, 1), date(2023, 3, 31))));
+    }
+
+    #[test]
+    fn test_last_month_in_january() {
+        let calendar = calendar(date(2023, 1, 10), None);
+        assert_eq!(calendar.range("last month"), Ok(range(date(2022, 12, 1), date(2022, 12, 31))));
+    }
+
+    #[test]
+    fn test_season_across_new_year() {
+        let calendar = calendar(date(2023, 2, 15), None);
+        assert_eq!(calendar.range("winter 2022"), Ok(range(date(2022, 11, 1), date(2023, 2, 28))));
+        assert_eq!(calendar.range("Winter"), Ok(range(date(2023, 11, 1), date(2024, 2, 28))));
+    }
+
+    #[test]
+    fn test_bad_input() {
+        let calendar = calendar(date(2023, 2, 15), None);
+        for expression in ["2022-13", "2022-q5", "2022-02-30", "summer", "spring twenty", "next month", ""] {
+            assert!(calendar.range(expression).is_err(), "{}", expression);
+        }
+        assert!(calendar.between("2022-06", "2022-05").is_err());
+        assert!(calendar.range("since last billing").is_err());
+        let billed = self::calendar(date(2023, 2, 15), Some(date(2023, 2, 15)));
+        assert!(billed.range("since last billing").is_err());
+    }
+
+    #[test]
+    fn test_validate_season() {
+        assert!(season("winter", "11-01", "02-28").validate().is_ok());
+        assert!(season("year", "01-01", "12-31").validate().is_ok());
+        for day in ["02-30", "13-01", "00-10", "02-29", "2-1", "0101", "11-01-2022"] {
+            assert!(season("bad", day, "12-31").validate().is_err(), "{}", day);
+            assert!(season("bad", "01-01", day).validate().is_err(), "{}", day);
+        }
+    }
+
+    #[test]
+    fn test_last_billing_file() {
+        let folder = std::env::temp_dir().join(format!("tkassa-last-billing-{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let folder = folder.to_str().unwrap();
+        assert_eq!(last_billing(folder), None);
+        fs::write(Path::new(folder).join(LAST_BILLING_FILE), "2022-06-30\n").unwrap();
+        assert_eq!(last_billing(folder), Some(date(2022, 6, 30)));
+        // An earlier range does not move the last billing back.
+        record_billing(folder, &range(date(2022, 1, 1), date(2022, 3, 31)));
+        assert_eq!(last_billing(folder), Some(date(2022, 6, 30)));
+        record_billing(folder, &range(date(2022, 7, 1), date(2022, 12, 31)));
+        assert_eq!(last_billing(folder), Some(date(2022, 12, 31)));
+        fs::write(Path::new(folder).join(LAST_BILLING_FILE), "June 30").unwrap();
+        assert_eq!(last_billing(folder), None);
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
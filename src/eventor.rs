@@ -1,11 +1,18 @@
-use reqwest::{blocking::Client, IntoUrl};
-use serde::Serialize;
+use crate::iof::{Document, IofVersion};
+use crate::source::{content_hash, Source};
+use reqwest::blocking::Client;
 use std::collections::hash_map::DefaultHasher;
-use std::io::ErrorKind;
-use std::{fs::read_to_string, hash::Hash, hash::Hasher, path::Path};
+use std::{fs::read_to_string, hash::Hash, hash::Hasher, path::Path, time::SystemTime};
+
+// The query parameter asking Eventor for IOF XML 3.0 instead of the default 2.0.3.
+const IOF_VERSION_PARAMETER: &str = "version";
+
+// The folder within the cache folder with an immutable copy of every response, named by its hash.
+const RESPONSES_FOLDER: &str = "responses";
 
 pub struct EventorClient<'a> {
     api_key: &'a str,
+    iof_version: IofVersion,
     verbose: bool,
     cache_folder: &'a Path,
     client: reqwest::blocking::Client,
@@ -15,26 +22,41 @@ impl<'a> EventorClient<'a> {
     pub fn new(api_key: &'a str, cache_folder: &'a str, verbose: bool) -> EventorClient<'a> {
         EventorClient {
             api_key,
+            iof_version: IofVersion::V2_0_3,
             verbose,
             cache_folder: Path::new(cache_folder),
             client: Client::new(),
         }
     }
 
-    pub fn request<T: Serialize + ?Sized, U: IntoUrl>(
+    // The responses can be in either version, since the parsers detect the version of each document.
+    pub fn with_iof_version(self, iof_version: IofVersion) -> EventorClient<'a> {
+        EventorClient { iof_version, ..self }
+    }
+}
+
+impl Source for EventorClient<'_> {
+    // The cache file for a request is replaced when the event changes, so every response is also
+    // kept in the responses folder under its content hash, and the cache key is that file's path
+    // within the cache folder.
+    fn request_modified_since(
         &self,
-        url: U,
-        parameters: &T,
-    ) -> xmltree::Element {
+        url: &str,
+        parameters: &[(&str, &str)],
+        modified: Option<SystemTime>,
+    ) -> Result<(Document, String), String> {
         if self.verbose {
-            println!("Eventor request: {}", url.as_str());
+            eprintln!("Eventor request: {}", url);
         }
 
-        let request = self
+        let mut request = self
             .client
             .get(url)
             .header("ApiKey", self.api_key)
             .query(parameters);
+        if self.iof_version != IofVersion::V2_0_3 {
+            request = request.query(&[(IOF_VERSION_PARAMETER, self.iof_version.to_string())]);
+        }
 
         let mut hasher = DefaultHasher::new();
         format!("{:?}", request).hash(&mut hasher);
@@ -42,42 +64,62 @@ impl<'a> EventorClient<'a> {
         let file_name = format!("{}.cache.xml", request_hash);
 
         let cache_path = self.cache_folder.join(Path::new(&file_name));
-        let result = if cache_path.exists() {
+        let stale = match (modified, cache_path.metadata().and_then(|m| m.modified())) {
+            (Some(modified), Ok(cached)) => cached < modified,
+            _ => false,
+        };
+        if stale && self.verbose {
+            eprintln!("\tThe event has been changed since {:?} was cached.", cache_path);
+        }
+        let result = if cache_path.exists() && !stale {
             if self.verbose {
-                println!("\tReading from cache at {:?}.", cache_path);
+                eprintln!("\tReading from cache at {:?}.", cache_path);
             }
-            read_to_string(cache_path)
+            read_to_string(&cache_path)
+                .map_err(|error| format!("Unable to read the cached response {:?}: {}", cache_path, error))
         } else {
             if self.verbose {
-                println!(
+                eprintln!(
                     "\tPerforming request to Eventor. Will save to cache at {:?}.",
                     cache_path
                 );
             }
             request
                 .send()
-                .expect("Unable to construct request to Eventor server.")
-                .text()
-                .and_then(|s: String| -> Result<String, reqwest::Error> {
-                    if let Err(_) = std::fs::write(cache_path, s.clone()) {
-                        println!("\tUnable to save request data.");
+                .and_then(|response| response.text())
+                .inspect(|s: &String| {
+                    if std::fs::write(&cache_path, s).is_err() {
+                        eprintln!("\tUnable to save request data.");
                     }
-                    Ok(s)
-                })
-                .map_err(|error: reqwest::Error| -> std::io::Error {
-                    std::io::Error::new(ErrorKind::Other, error.to_string())
                 })
-                .and_then(|s: String| -> Result<String, std::io::Error> {
+                .map_err(|error| format!("The request to Eventor for {} failed: {}", url, error))
+                .and_then(|s: String| -> Result<String, String> {
                     if s.contains("Internal server error") {
-                        Err(std::io::Error::new(ErrorKind::Other, "XML file from Eventor contains 'Internal server error'".to_string()))
+                        Err(format!("The response from Eventor for {} contains 'Internal server error'.", url))
                     } else {
                         Ok(s)
                     }
                 })
         };
 
-        result
-            .map(|s| xmltree::Element::parse(s.as_bytes()).expect("Invalid XML from Eventor."))
-            .expect("Unable to load XML.")
+        // The response is read from its copy, so that it is not kept in memory.
+        let text = result?;
+        let cache_key = format!("{}/{}.xml", RESPONSES_FOLDER, content_hash(&text));
+        let response_path = self.cache_folder.join(&cache_key);
+        if !response_path.exists() {
+            let saved = std::fs::create_dir_all(self.cache_folder.join(RESPONSES_FOLDER))
+                .and_then(|_| std::fs::write(&response_path, &text));
+            if saved.is_err() {
+                eprintln!("\tUnable to save a copy of the response at {:?}.", response_path);
+            }
+        }
+        let document = if response_path.exists() {
+            drop(text);
+            Document::open(&response_path)
+        } else {
+            Document::parse(text)
+        };
+        let document = document.map_err(|error| format!("Invalid XML from Eventor for {}: {}", url, error))?;
+        Ok((document, cache_key))
     }
 }
@@ -0,0 +1,198 @@
+use crate::billing::{BillableEvent, TeamFeeSplit};
+use crate::iof::{Competitor, CompetitorStatus, Entry, EntryFee, Event, EventClass, ValueOperator};
+
+// Where the fees for a billable line were taken from.
+pub enum FeeSource<'a> {
+    PreEntry(&'a Entry),
+    // The birth year used, and whether it had to be guessed.
+    DirectEntry(&'a EventClass, u64, bool),
+}
+
+// What is known about the line being explained, besides the fees.
+pub struct Context<'a> {
+    pub person: &'a Competitor,
+    pub event: &'a Event,
+    pub event_class: Option<&'a EventClass>,
+    pub entry_fees: &'a [EntryFee],
+    pub waived: &'a [CompetitorStatus],
+}
+
+pub struct TeamShare<'a> {
+    pub name: &'a str,
+    pub entry: Option<&'a Entry>,
+    pub fee: (f64, f64),
+    // Including runners from other clubs.
+    pub team_size: usize,
+    pub split: TeamFeeSplit,
+}
+
+// Prints how the fees for a person's line at an event were arrived at. The intermediate values
+// are the ones actually used when billing, so the explanation always matches the numbers.
+pub fn individual(
+    context: &Context,
+    source: &FeeSource,
+    fee_ids: &[u64],
+    races: &[(u64, CompetitorStatus)],
+    line: &BillableEvent,
+) {
+    let (event, entry_fees, waived) = (context.event, context.entry_fees, context.waived);
+    header(context);
+
+    let considered: Vec<u64> = match source {
+        FeeSource::PreEntry(entry) => {
+            println!(
+                "Source: pre-entry {} in Eventor, using the fees of the entry.",
+                entry.id.map_or("?".to_string(), |id| id.to_string())
+            );
+            entry.fee_ids.clone()
+        }
+        FeeSource::DirectEntry(class, birth_year, guessed) => {
+            println!(
+                "Source: no pre-entry found, using the fees for direct entry in class {}.",
+                class.name
+            );
+            if *guessed {
+                println!("Birth year unknown, {} was used instead.", birth_year);
+            } else {
+                println!("Born {}.", birth_year);
+            }
+            class.fee_ids.clone()
+        }
+    };
+
+    println!("Fees, in the order they are applied:");
+    for fee_id in considered.iter() {
+        let fee = match entry_fees.iter().find(|fee| fee.id == *fee_id) {
+            Some(fee) => fee,
+            None => {
+                println!("  {}: not defined for the event", fee_id);
+                continue;
+            }
+        };
+        let outcome = if fee.is_card_rental() {
+            "card rental, billed separately".to_string()
+        } else if !fee_ids.contains(fee_id) {
+            match source {
+                FeeSource::DirectEntry(_, birth_year, _) => {
+                    format!("skipped, does not apply to those born {}", birth_year)
+                }
+                FeeSource::PreEntry(_) => "skipped".to_string(),
+            }
+        } else if let Some(event_race_id) = fee.event_race_id.filter(|_| event.is_multi_day()) {
+            match races.iter().find(|(id, _)| *id == event_race_id) {
+                Some((_, status)) if waived.contains(status) => {
+                    format!("waived, status {} on stage {}", status, stage(event, &event_race_id))
+                }
+                Some(_) => format!("applied to stage {}", stage(event, &event_race_id)),
+                None => format!("skipped, no result on stage {}", stage(event, &event_race_id)),
+            }
+        } else if races.iter().all(|(_, status)| waived.contains(status)) {
+            "waived because of the competitor status".to_string()
+        } else {
+            "applied".to_string()
+        };
+        println!("  {}: {}", describe(fee), outcome);
+    }
+
+    println!("Races:");
+    for (event_race_id, status) in races.iter() {
+        let race = event.race(event_race_id);
+        let details: Vec<String> = [
+            race.and_then(|race| race.name.clone()),
+            race.and_then(|race| race.distance.clone()),
+            event.date_for_race(event_race_id).map(|time| time.date.to_string()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        println!(
+            "  Stage {} ({}): {}{}",
+            stage(event, event_race_id),
+            details.join(", "),
+            status,
+            if waived.contains(status) { ", not billed" } else { "" }
+        );
+    }
+
+    totals(line);
+}
+
+// Prints how the fee for a relay runner was arrived at.
+pub fn team(context: &Context, team: &TeamShare, status: &CompetitorStatus, line: &BillableEvent) {
+    header(context);
+    println!("Team: {}, leg {}", team.name, line.leg.map_or("?".to_string(), |leg| leg.to_string()));
+    match team.entry {
+        Some(entry) => println!(
+            "Source: team entry {} in Eventor, using the fees of the entry.",
+            entry.id.map_or("?".to_string(), |id| id.to_string())
+        ),
+        None => println!("Source: no team entry found, using the fees for direct entry in the class."),
+    }
+    println!("Team fee: normal {}, late {}", team.fee.0, team.fee.1);
+    match team.split {
+        TeamFeeSplit::Equal => println!("Split: equal share for each of the {} runners.", team.team_size),
+        TeamFeeSplit::Club => println!("Split: paid by the club."),
+        TeamFeeSplit::Captain => println!(
+            "Split: the club's first runner not waived pays the club's runners' shares, of {} runners in all.",
+            team.team_size
+        ),
+    }
+    if context.waived.contains(status) {
+        println!("Status {} is not billed.", status);
+    }
+    totals(line);
+}
+
+fn header(context: &Context) {
+    println!(
+        "{} {} ({}) at {} ({}), class {}",
+        context.person.given,
+        context.person.family,
+        context.person.id.map_or("?".to_string(), |id| id.to_string()),
+        context.event.name,
+        context.event.id,
+        context.event_class.map_or("unknown".to_string(), |class| class.name.clone())
+    );
+    for entry_break in context.event.entry_breaks.iter() {
+        println!(
+            "Entry period: {} to {}",
+            entry_break.valid_from.map_or("?".to_string(), |time| time.date.to_string()),
+            entry_break.valid_to.map_or("?".to_string(), |time| time.date.to_string())
+        );
+    }
+    if context.event.is_cancelled() {
+        println!("The event has been cancelled.");
+    }
+}
+
+fn totals(line: &BillableEvent) {
+    let stage_normal: f64 = line.stages.iter().map(|stage| stage.normal_fee).sum();
+    let stage_late: f64 = line.stages.iter().map(|stage| stage.late_fee).sum();
+    println!("Result:");
+    println!("  Normal fee: {}", line.normal_fee + stage_normal);
+    println!("  Late fee: {}", line.late_fee + stage_late);
+    for extra in line.extras.iter() {
+        println!("  {}: {} ({})", extra.kind, extra.amount, extra.description);
+    }
+    println!();
+}
+
+fn stage(event: &Event, event_race_id: &u64) -> String {
+    event
+        .stage_number(event_race_id)
+        .map_or("?".to_string(), |number| number.to_string())
+}
+
+fn describe(fee: &EntryFee) -> String {
+    let amount = match fee.operator {
+        ValueOperator::Fixed => format!("{}", fee.amount),
+        ValueOperator::Percent => format!("{}%", fee.amount),
+    };
+    let ages = match (fee.from_year_of_birth, fee.to_year_of_birth) {
+        (None, None) => "all ages".to_string(),
+        (Some(from), None) => format!("born {} or later", from),
+        (None, Some(to)) => format!("born {} or earlier", to),
+        (Some(from), Some(to)) => format!("born {}-{}", from, to),
+    };
+    format!("{} '{}', {}, {}", fee.id, fee.name, amount, ages)
+}
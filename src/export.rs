@@ -0,0 +1,72 @@
+use crate::iof::{self, xml};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+// What to write as IOF XML instead of billing. The documents are written from the model in the xml
+// module, so elements not in it, such as split times, radio controls and addresses, are dropped.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Export {
+    // The club's results, one result list per event, in a file of its own when there are several.
+    Results,
+    // The club's entries for all events.
+    Entries,
+}
+
+impl FromStr for Export {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "results" => Ok(Export::Results),
+            "entries" => Ok(Export::Entries),
+            _ => Err("Unrecognized export, should be results or entries."),
+        }
+    }
+}
+
+fn to_xml<T: Serialize>(root: &str, document: &T) -> String {
+    let body = quick_xml::se::to_string_with_root(root, document)
+        .expect("Unable to write IOF XML.");
+    format!("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n{}\n", body)
+}
+
+// Only the club's persons and teams, as when billing. A team is kept whole if any of its runners
+// is from the club, and classes with none of the club's results are left out.
+pub fn club_results(result_list: &mut xml::ResultList, organisation_id: u64) {
+    for class_result in result_list.class_results.iter_mut() {
+        class_result.person_results.retain(|person_result| iof::is_club(person_result.organisation.as_ref(), organisation_id));
+        class_result.team_results.retain(|team| {
+            team.person_results.iter().any(|runner| iof::is_club(runner.organisation.as_ref(), organisation_id))
+        });
+    }
+    result_list
+        .class_results
+        .retain(|class_result| !class_result.person_results.is_empty() || !class_result.team_results.is_empty());
+}
+
+// A result list is for a single event, as IOF XML 2.0.3 has no list of result lists.
+pub fn result_list(mut result_list: xml::ResultList) -> String {
+    result_list.iof_version = xml::IofVersionElement::v2_0_3();
+    to_xml("ResultList", &result_list)
+}
+
+// Writes a result list per event to `folder`, named by the event id. Returns the number written.
+pub fn write_result_lists(result_lists: Vec<(u64, xml::ResultList)>, folder: &str) -> Result<usize, String> {
+    fs::create_dir_all(folder).map_err(|e| format!("Unable to create export folder {}: {}", folder, e))?;
+    let count = result_lists.len();
+    for (event_id, list) in result_lists.into_iter() {
+        let path = Path::new(folder).join(format!("{}.xml", event_id));
+        fs::write(&path, result_list(list)).map_err(|e| format!("Unable to write result list {:?}: {}", path, e))?;
+    }
+    Ok(count)
+}
+
+pub fn entry_list(entries: Vec<xml::Entry>) -> String {
+    let document = xml::EntryList {
+        iof_version: xml::IofVersionElement::v2_0_3(),
+        entries,
+    };
+    to_xml("EntryList", &document)
+}
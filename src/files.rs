@@ -0,0 +1,322 @@
+use crate::iof::{self, Document, IofVersion};
+use crate::source::{file_hash, Source};
+use crate::{ENTRIES, ENTRY_FEES, EVENT, EVENTS, EVENT_CLASSES, ORGANISATION_RESULTS, PERSONS, SERVICE_REQUESTS};
+use chrono::NaiveDate;
+use quick_xml::events::{BytesStart, Event as XmlEvent};
+use quick_xml::Reader;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use xmltree::Element;
+
+// IOF XML files on disk, for billing without an API key or for auditing a past season. The files
+// can be anywhere in the folder. What a file holds is told by its root element, and the event it is
+// for by the event in the file, or else by the name of the folder it is in, which is then the event id.
+// The club's members are read from a person list anywhere in the folder.
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+enum Kind {
+    Results,
+    Entries,
+    Classes,
+    Fees,
+    ServiceRequests,
+}
+
+impl Kind {
+    fn from_root(name: &str) -> Option<Kind> {
+        match name {
+            "ResultList" => Some(Kind::Results),
+            "EntryList" => Some(Kind::Entries),
+            "EventClassList" | "ClassList" => Some(Kind::Classes),
+            "EntryFeeList" => Some(Kind::Fees),
+            "ServiceRequestList" => Some(Kind::ServiceRequests),
+            _ => None,
+        }
+    }
+
+    // An empty document, for events that have no file of this kind.
+    fn empty(&self, version: IofVersion) -> String {
+        let root = match (self, version) {
+            (Kind::Results, _) => "ResultList",
+            (Kind::Entries, _) => "EntryList",
+            (Kind::Classes, IofVersion::V2_0_3) => "EventClassList",
+            (Kind::Classes, IofVersion::V3_0) => "ClassList",
+            (Kind::Fees, _) => "EntryFeeList",
+            (Kind::ServiceRequests, _) => "ServiceRequestList",
+        };
+        match version {
+            IofVersion::V2_0_3 => format!("<{}/>", root),
+            IofVersion::V3_0 => format!("<{} iofVersion=\"3.0\"/>", root),
+        }
+    }
+}
+
+pub struct LocalFiles {
+    folder: PathBuf,
+    verbose: bool,
+    version: IofVersion,
+    events: BTreeMap<u64, Element>,
+    files: HashMap<(u64, Kind), PathBuf>,
+    person_list: Option<PathBuf>,
+}
+
+// EventId in IOF XML 2.0.3, the first Id in 3.0.
+fn event_id(event: &Element) -> Option<u64> {
+    event
+        .get_child("EventId")
+        .or_else(|| event.get_child("Id"))
+        .and_then(|id| id.get_text())
+        .and_then(|id| id.trim().parse::<u64>().ok())
+}
+
+fn xml_files(folder: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = fs::read_dir(folder).map_err(|e| format!("Unable to read folder {:?}: {}", folder, e))?;
+    for entry in entries {
+        let path = entry.map_err(|e| format!("Unable to read folder {:?}: {}", folder, e))?.path();
+        if path.is_dir() {
+            xml_files(&path, files)?;
+        } else if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("xml")) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn to_text(element: &Element) -> String {
+    let mut text: Vec<u8> = vec![];
+    element.write(&mut text).expect("Unable to write XML.");
+    String::from_utf8(text).expect("Invalid UTF-8 in XML.")
+}
+
+// What a file holds, read from its start: the root element, and the events, which are either the
+// whole of an event list or at the start of any other list. The rest of the file is left unread,
+// until the list is needed.
+struct Head {
+    root: String,
+    version: IofVersion,
+    events: Vec<Element>,
+    event_id: Option<u64>,
+}
+
+fn head(path: &Path) -> Result<Head, String> {
+    let problem = |e: &dyn std::fmt::Display| format!("Invalid XML in {:?}: {}", path, e);
+    let file = File::open(path).map_err(|e| format!("Unable to read {:?}: {}", path, e))?;
+    let mut reader = Reader::from_reader(BufReader::new(file));
+    let mut buffer = vec![];
+    let (root, version) = loop {
+        buffer.clear();
+        match reader.read_event_into(&mut buffer).map_err(|e| problem(&e))? {
+            XmlEvent::Start(start) | XmlEvent::Empty(start) => {
+                let version = match start.try_get_attribute("iofVersion") {
+                    Ok(Some(version)) if version.value.starts_with(b"3") => IofVersion::V3_0,
+                    _ => IofVersion::V2_0_3,
+                };
+                break (String::from_utf8_lossy(start.name().as_ref()).to_string(), version);
+            }
+            XmlEvent::Eof => return Err(problem(&"The XML document is empty.")),
+            _ => {}
+        }
+    };
+    let element = |reader: &mut Reader<BufReader<File>>, start: BytesStart| -> Result<Element, String> {
+        let element = iof::read_element(reader, start).map_err(|e| problem(&e))?;
+        Element::parse(element.as_slice()).map_err(|e| problem(&e))
+    };
+
+    let mut head = Head { root, version, events: vec![], event_id: None };
+    // A file with a single event is small, and read whole.
+    if head.root == "Event" {
+        head.events.push(Element::parse(File::open(path).map_err(|e| problem(&e))?).map_err(|e| problem(&e))?);
+    }
+    while head.root != "Event" {
+        buffer.clear();
+        match reader.read_event_into(&mut buffer).map_err(|e| problem(&e))? {
+            XmlEvent::Start(start) => match start.name().as_ref() {
+                b"Event" => head.events.push(element(&mut reader, start.into_owned())?),
+                b"EventId" => {
+                    let id = element(&mut reader, start.into_owned())?;
+                    head.event_id = id.get_text().and_then(|id| id.trim().parse::<u64>().ok());
+                }
+                // The version of IOF XML 2.0.3 comes first, and everything in an event list is read.
+                name if name == b"IOFVersion" || head.root == "EventList" => {
+                    let end = start.to_end().into_owned();
+                    reader.read_to_end_into(end.name(), &mut vec![]).map_err(|e| problem(&e))?;
+                }
+                _ => break,
+            },
+            XmlEvent::End(_) | XmlEvent::Eof => break,
+            _ => {}
+        }
+    }
+    if version == IofVersion::V3_0 {
+        for event in head.events.iter_mut() {
+            event.attributes.insert("iofVersion".to_string(), "3.0".to_string());
+        }
+    }
+    Ok(head)
+}
+
+impl LocalFiles {
+    // Only the start of each file is read here, to tell what it holds and which event it is for.
+    pub fn new(folder: &str, verbose: bool) -> Result<LocalFiles, String> {
+        let folder = PathBuf::from(folder);
+        let mut paths: Vec<PathBuf> = vec![];
+        xml_files(&folder, &mut paths)?;
+        paths.sort();
+
+        let mut versions: Vec<IofVersion> = vec![];
+        let mut events: BTreeMap<u64, Element> = BTreeMap::new();
+        let mut files: HashMap<(u64, Kind), PathBuf> = HashMap::new();
+        let mut person_list: Option<PathBuf> = None;
+        for path in paths {
+            let head = head(&path)?;
+
+            if head.root == "PersonList" {
+                if let Some(existing) = person_list.replace(path.clone()) {
+                    return Err(format!("Both {:?} and {:?} are person lists.", existing, path));
+                }
+                continue;
+            }
+            let kind = match head.root.as_str() {
+                "Event" | "EventList" => None,
+                name => match Kind::from_root(name) {
+                    Some(kind) => Some(kind),
+                    None => {
+                        if verbose {
+                            eprintln!("Skipping {:?}, which is not a list tkassa reads.", path);
+                        }
+                        continue;
+                    }
+                },
+            };
+            if !versions.contains(&head.version) {
+                versions.push(head.version);
+            }
+
+            // Events are taken from event lists, and from the other lists if not found elsewhere.
+            let mut event_ids: Vec<u64> = vec![];
+            for event in head.events.into_iter() {
+                let id = event_id(&event).ok_or(format!("Event without an id in {:?}.", path))?;
+                event_ids.push(id);
+                if kind.is_none() || !events.contains_key(&id) {
+                    events.insert(id, event);
+                }
+            }
+
+            if let Some(kind) = kind {
+                let id = event_ids
+                    .first()
+                    .copied()
+                    .or(head.event_id)
+                    .or_else(|| path.parent().and_then(|parent| parent.file_name()).and_then(|name| name.to_str()?.parse::<u64>().ok()))
+                    .ok_or(format!("Unable to tell which event {:?} is for. Put it in a folder named by the event id.", path))?;
+                if let Some(existing) = files.insert((id, kind), path.clone()) {
+                    return Err(format!("Both {:?} and {:?} are {:?} for event {}.", existing, path, kind, id));
+                }
+            }
+        }
+
+        let version = match versions.as_slice() {
+            [] => IofVersion::V2_0_3,
+            [version] => *version,
+            _ => return Err(format!("The files in {:?} mix IOF XML 2.0.3 and 3.0.", folder)),
+        };
+        Ok(LocalFiles { folder, verbose, version, events, files, person_list })
+    }
+
+    // Reads a file as it is needed, with its path in any error. The key is the path of the file
+    // within the folder and the hash of its content.
+    fn read(&self, path: &Path) -> Result<(Document, String), String> {
+        if self.verbose {
+            eprintln!("\tReading {:?}.", path);
+        }
+        let hash = file_hash(path).map_err(|e| format!("Unable to read {:?}: {}", path, e))?;
+        let key = format!("{}@{}", path.strip_prefix(&self.folder).unwrap_or(path).to_string_lossy(), hash);
+        let document = Document::open(path).map_err(|e| format!("Invalid XML in {:?}: {}", path, e))?;
+        Ok((document, key))
+    }
+
+    // Documents made here, rather than read from a file, have no key.
+    fn made(text: String) -> Result<(Document, String), String> {
+        let document = Document::parse(text).map_err(|e| format!("Invalid XML made from the files: {}", e))?;
+        Ok((document, String::new()))
+    }
+
+    fn file(&self, event_id: u64, kind: Kind) -> Result<(Document, String), String> {
+        match self.files.get(&(event_id, kind)) {
+            Some(path) => self.read(path),
+            // IOF XML 3.0 has the fees in the classes, when there is no list of fees.
+            None if kind == Kind::Fees && self.version == IofVersion::V3_0 => self.file(event_id, Kind::Classes),
+            None => LocalFiles::made(kind.empty(self.version)),
+        }
+    }
+
+    fn person_list(&self) -> Result<(Document, String), String> {
+        match &self.person_list {
+            Some(path) => self.read(path),
+            None if self.version == IofVersion::V3_0 => LocalFiles::made("<PersonList iofVersion=\"3.0\"/>".to_string()),
+            None => LocalFiles::made("<PersonList/>".to_string()),
+        }
+    }
+
+    fn event(&self, event_id: u64) -> Result<(Document, String), String> {
+        let event = self
+            .events
+            .get(&event_id)
+            .ok_or(format!("No file in {:?} has event {}.", self.folder, event_id))?;
+        LocalFiles::made(to_text(event))
+    }
+
+    // Events with a race in the range.
+    fn event_list(&self, from: Option<&str>, to: Option<&str>) -> Result<(Document, String), String> {
+        let date = |date: Option<&str>| date.and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+        let (from, to) = (date(from).unwrap_or(NaiveDate::MIN), date(to).unwrap_or(NaiveDate::MAX));
+
+        let mut list = Element::new("EventList");
+        if self.version == IofVersion::V3_0 {
+            list.attributes.insert("iofVersion".to_string(), "3.0".to_string());
+        }
+        for event_id in self.events.keys() {
+            let event = iof::event(&self.event(*event_id)?.0)
+                .map_err(|e| format!("Unable to read event {} in {:?}: {}", event_id, self.folder, e))?;
+            if event.races.iter().any(|race| race.date.date >= from && race.date.date <= to) {
+                list.children.push(xmltree::XMLNode::Element(self.events[event_id].clone()));
+            }
+        }
+        LocalFiles::made(to_text(&list))
+    }
+}
+
+impl Source for LocalFiles {
+    // The key is the path of the file within the folder and the hash of its content, and empty if
+    // the answer does not come from a single file.
+    fn request_modified_since(
+        &self,
+        url: &str,
+        parameters: &[(&str, &str)],
+        _modified: Option<SystemTime>,
+    ) -> Result<(Document, String), String> {
+        let parameter = |name: &str| parameters.iter().find(|(n, _)| *n == name).map(|(_, value)| *value);
+        let event_id = parameter("eventId")
+            .or(parameter("eventIds"))
+            .or(url.strip_prefix(EVENT))
+            .or(url.strip_prefix(ENTRY_FEES))
+            .and_then(|id| id.parse::<u64>().ok())
+            .ok_or(format!("Missing event id in request for local files: {}", url));
+
+        let kind = match url {
+            EVENTS => return self.event_list(parameter("fromDate"), parameter("toDate")),
+            ORGANISATION_RESULTS => Kind::Results,
+            EVENT_CLASSES => Kind::Classes,
+            ENTRIES => Kind::Entries,
+            SERVICE_REQUESTS => Kind::ServiceRequests,
+            url if url.starts_with(PERSONS) => return self.person_list(),
+            url if url.starts_with(ENTRY_FEES) => Kind::Fees,
+            url if url.starts_with(EVENT) => return self.event(event_id?),
+            _ => return Err(format!("Unsupported request for local files: {}", url)),
+        };
+        self.file(event_id?, kind)
+    }
+}
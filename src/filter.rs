@@ -0,0 +1,132 @@
+use crate::iof::{Discipline, Event, EventClassification, EventStatus};
+use serde::{Deserialize, Deserializer};
+use std::str::FromStr;
+
+// A rule matching events. Every criterion given has to match, and a criterion with several values
+// matches if any of them does.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    #[serde(default)]
+    event: Vec<u64>,
+    #[serde(default, deserialize_with = "parsed")]
+    classification: Vec<EventClassification>,
+    #[serde(default, deserialize_with = "parsed")]
+    discipline: Vec<Discipline>,
+    #[serde(default)]
+    organiser: Vec<u64>,
+    #[serde(default, deserialize_with = "parsed")]
+    status: Vec<EventStatus>,
+    // Patterns for the event name, where * matches anything. Case is ignored.
+    #[serde(default)]
+    name: Vec<String>,
+    // Whether the event has its entry fees in Eventor, i.e. is paid through Eventor.
+    eventor_fees: Option<bool>,
+}
+
+// Decides which events are billed. With no include rules, every event is included.
+#[derive(Debug, Default)]
+pub struct EventFilter {
+    pub include: Vec<Rule>,
+    pub exclude: Vec<Rule>,
+}
+
+fn parsed<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr<Err = &'static str>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|value| value.parse::<T>().map_err(serde::de::Error::custom))
+        .collect()
+}
+
+fn parsed_values<T: FromStr<Err = &'static str>>(values: &str) -> Result<Vec<T>, &'static str> {
+    values.split(',').map(|value| value.parse::<T>()).collect()
+}
+
+fn ids(values: &str) -> Result<Vec<u64>, &'static str> {
+    values
+        .split(',')
+        .map(|value| value.parse::<u64>().map_err(|_| "Invalid id in event filter."))
+        .collect()
+}
+
+// A rule given on the command line, with a single criterion such as "classification=club,local".
+impl FromStr for Rule {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, values) = s
+            .split_once('=')
+            .ok_or("Event filters should be given as criterion=value, e.g. discipline=foot.")?;
+        let mut rule = Rule::default();
+        match key {
+            "event" => rule.event = ids(values)?,
+            "classification" => rule.classification = parsed_values(values)?,
+            "discipline" => rule.discipline = parsed_values(values)?,
+            "organiser" => rule.organiser = ids(values)?,
+            "status" => rule.status = parsed_values(values)?,
+            "name" => rule.name = values.split(',').map(|value| value.to_string()).collect(),
+            "eventor_fees" => rule.eventor_fees = Some(values.parse::<bool>().map_err(|_| "eventor_fees should be true or false.")?),
+            _ => return Err("Unrecognized event filter, should be one of event, classification, discipline, organiser, status, name or eventor_fees."),
+        }
+        Ok(rule)
+    }
+}
+
+impl Rule {
+    // `eventor_fees` is only needed if the rule asks for it, see `needs_eventor_fees`.
+    fn matches(&self, event: &Event, eventor_fees: Option<bool>) -> bool {
+        (self.event.is_empty() || self.event.contains(&event.id))
+            && (self.classification.is_empty()
+                || event.classification.is_some_and(|c| self.classification.contains(&c)))
+            && (self.discipline.is_empty()
+                || event.discipline.is_some_and(|d| self.discipline.contains(&d)))
+            && (self.organiser.is_empty()
+                || event.organiser_ids.iter().any(|id| self.organiser.contains(id)))
+            && (self.status.is_empty() || event.status.is_some_and(|s| self.status.contains(&s)))
+            && (self.name.is_empty() || self.name.iter().any(|pattern| name_matches(pattern, &event.name)))
+            && self.eventor_fees.is_none_or(|wanted| eventor_fees == Some(wanted))
+    }
+}
+
+impl EventFilter {
+    pub fn includes(&self, event: &Event, eventor_fees: Option<bool>) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|rule| rule.matches(event, eventor_fees)))
+            && !self.exclude.iter().any(|rule| rule.matches(event, eventor_fees))
+    }
+
+    // Finding out whether an event has its fees in Eventor takes a request, so it is only done
+    // when some rule depends on it.
+    pub fn needs_eventor_fees(&self) -> bool {
+        self.include
+            .iter()
+            .chain(self.exclude.iter())
+            .any(|rule| rule.eventor_fees.is_some())
+    }
+}
+
+// Matches a name against a pattern where * stands for any text, ignoring case.
+fn name_matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let name = name.to_lowercase();
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == name;
+    }
+
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !name.starts_with(first) || !name.ends_with(last) || name.len() < first.len() + last.len() {
+        return false;
+    }
+    let mut rest = &name[first.len()..name.len() - last.len()];
+    for part in parts[1..parts.len() - 1].iter() {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
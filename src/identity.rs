@@ -0,0 +1,223 @@
+use crate::billing::Person;
+use crate::iof::Competitor;
+use crate::roster::Roster;
+use crate::totals::Totals;
+use deunicode::deunicode;
+use serde::Deserialize;
+use std::fs;
+
+// The version of the alias file format that this version of tkassa reads.
+const VERSION: u64 = 1;
+
+// How alike two persons are, from least to most certain.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Likeness {
+    Different,
+    // The same given name and birth year, and a family name sharing a part, such as after a
+    // marriage that added a double-barrelled name.
+    Partial,
+    SameName,
+    SamePersonId,
+}
+
+// The parts of a name, without case, diacritics or punctuation, so that "Åsa Öberg-Lind" and
+// "asa oberg lind" compare equal.
+pub fn name_parts(name: &str) -> Vec<String> {
+    deunicode(name)
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| part.to_string())
+        .collect()
+}
+
+pub fn same_name(a: &Competitor, b: &Competitor) -> bool {
+    name_parts(&a.given) == name_parts(&b.given) && name_parts(&a.family) == name_parts(&b.family)
+}
+
+// Person ids decide when both are known. Otherwise the names must agree, and the birth years when
+// both are known.
+pub fn likeness(a: &Competitor, b: &Competitor) -> Likeness {
+    if let (Some(a_id), Some(b_id)) = (a.id, b.id) {
+        return if a_id == b_id { Likeness::SamePersonId } else { Likeness::Different };
+    }
+    let same_birth_year = match (a.birth_year, b.birth_year) {
+        (Some(a_year), Some(b_year)) if a_year != b_year => return Likeness::Different,
+        (Some(_), Some(_)) => true,
+        _ => false,
+    };
+    if same_name(a, b) {
+        return Likeness::SameName;
+    }
+    let (a_family, b_family) = (name_parts(&a.family), name_parts(&b.family));
+    if same_birth_year
+        && name_parts(&a.given).first() == name_parts(&b.given).first()
+        && a_family.iter().any(|part| b_family.contains(part))
+    {
+        Likeness::Partial
+    } else {
+        Likeness::Different
+    }
+}
+
+pub enum Resolution {
+    Unique(usize, Likeness),
+    // Equally likely candidates, by index.
+    Ambiguous(Vec<usize>),
+    NotFound,
+}
+
+// The candidate that is the person, taking the most certain likeness found.
+pub fn resolve<'a>(candidates: impl IntoIterator<Item = &'a Competitor>, competitor: &Competitor) -> Resolution {
+    let likenesses: Vec<Likeness> = candidates.into_iter().map(|candidate| likeness(candidate, competitor)).collect();
+    for level in [Likeness::SamePersonId, Likeness::SameName, Likeness::Partial] {
+        let found: Vec<usize> = (0..likenesses.len()).filter(|index| likenesses[*index] == level).collect();
+        match found.len() {
+            0 => continue,
+            1 => return Resolution::Unique(found[0], level),
+            _ => return Resolution::Ambiguous(found),
+        }
+    }
+    Resolution::NotFound
+}
+
+// Names or person ids that a person also appears under, such as a name before marriage or a
+// duplicate account in Eventor, kept by the club in a TOML file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Aliases {
+    version: u64,
+    #[serde(default)]
+    alias: Vec<Alias>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Alias {
+    // The person id to use.
+    person: u64,
+    // Another person id for the same person.
+    id: Option<u64>,
+    // Another name for the same person.
+    given: Option<String>,
+    family: Option<String>,
+}
+
+impl Alias {
+    fn matches(&self, competitor: &Competitor) -> bool {
+        if self.id.is_some() && competitor.id == self.id {
+            return true;
+        }
+        // Names only apply to persons without another id.
+        match (&self.given, &self.family) {
+            (Some(given), Some(family)) if competitor.id.is_none() || competitor.id == Some(self.person) => {
+                let name = Competitor { id: None, given: given.clone(), family: family.clone(), birth_year: None };
+                same_name(&name, competitor)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Aliases {
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read alias file {}: {}", path, e))?;
+        let aliases: Aliases = toml::from_str(&contents)
+            .map_err(|e| format!("Unable to parse alias file {}: {}", path, e))?;
+        if aliases.version != VERSION {
+            return Err(format!(
+                "Alias file {} has version {}, but only version {} is supported.",
+                path, aliases.version, VERSION
+            ));
+        }
+        for alias in aliases.alias.iter() {
+            if alias.id.is_none() && (alias.given.is_none() || alias.family.is_none()) {
+                return Err(format!(
+                    "The alias for person {} in alias file {} needs either an id or a given and family name.",
+                    alias.person, path
+                ));
+            }
+        }
+        Ok(aliases)
+    }
+
+    fn apply(&self, competitor: &Competitor) -> Competitor {
+        match self.alias.iter().find(|alias| alias.matches(competitor)) {
+            Some(alias) => Competitor { id: Some(alias.person), ..competitor.clone() },
+            None => competitor.clone(),
+        }
+    }
+}
+
+// Finds the persons billed for the competitors in results and other sources. Matches that are not
+// certain are noted as warnings.
+pub struct Resolver<'a> {
+    aliases: &'a Aliases,
+    roster: Option<&'a Roster>,
+    pub warnings: Vec<String>,
+}
+
+fn describe(competitor: &Competitor) -> String {
+    let mut description = format!("{} {}", competitor.given, competitor.family);
+    if let Some(birth_year) = competitor.birth_year {
+        description += &format!(", born {}", birth_year);
+    }
+    if let Some(id) = competitor.id {
+        description += &format!(" ({})", id);
+    }
+    description
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(aliases: &'a Aliases, roster: Option<&'a Roster>) -> Resolver<'a> {
+        Resolver { aliases, roster, warnings: vec![] }
+    }
+
+    fn warn(&mut self, warning: String) {
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+    }
+
+    // Finds the person among those already billed, or adds a new one. Returns the index in `persons`.
+    pub fn person_index(&mut self, persons: &mut Vec<Person>, competitor: &Competitor) -> usize {
+        let mut competitor = self.aliases.apply(competitor);
+        // A member of the club can be told by the roster, even without a person id.
+        if competitor.id.is_none() {
+            if let Some(member) = self.roster.and_then(|roster| roster.find(&competitor)) {
+                competitor.id = member.id;
+                competitor.birth_year = competitor.birth_year.or(member.birth_year);
+            }
+        }
+
+        if let Some(index) = persons.iter().position(|p| p.person == competitor) {
+            return index;
+        }
+        match resolve(persons.iter().map(|p| &p.person), &competitor) {
+            Resolution::Unique(index, Likeness::Partial) => {
+                self.warn(format!(
+                    "Taking {} to be the same person as {}.",
+                    describe(&competitor),
+                    describe(&persons[index].person)
+                ));
+                index
+            }
+            Resolution::Unique(index, _) => index,
+            Resolution::Ambiguous(indices) => {
+                let candidates: Vec<String> = indices.iter().map(|index| describe(&persons[*index].person)).collect();
+                self.warn(format!(
+                    "{} could be any of {}, and is listed separately. Give the person id in an alias file to tell them apart.",
+                    describe(&competitor),
+                    candidates.join(", ")
+                ));
+                persons.push(Person { person: competitor, billable: vec![], member: None, totals: Totals::default() });
+                persons.len() - 1
+            }
+            Resolution::NotFound => {
+                persons.push(Person { person: competitor, billable: vec![], member: None, totals: Totals::default() });
+                persons.len() - 1
+            }
+        }
+    }
+}
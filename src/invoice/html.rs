@@ -0,0 +1,94 @@
+use super::{amount, payment_text, person_heading, summary, swish_modules, Invoice, InvoiceSettings};
+use crate::output::items;
+
+// Used unless the club gives its own template.
+pub const DEFAULT_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{{title}}</title>
+<style>
+body { font-family: Helvetica, Arial, sans-serif; font-size: 10pt; margin: 2em; }
+.letterhead { border-bottom: 1px solid #888; margin-bottom: 1.5em; padding-bottom: 0.5em; }
+.letterhead p { margin: 0; }
+.letterhead p:first-child { font-weight: bold; font-size: 14pt; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { text-align: left; padding: 0.15em 0.8em 0.15em 0; }
+td.amount, th.amount { text-align: right; }
+tr.total td { font-weight: bold; border-top: 1px solid #888; }
+</style>
+</head>
+<body>
+<div class="letterhead">{{letterhead}}</div>
+{{invoice}}
+</body>
+</html>
+"#;
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+pub fn invoice(invoice: &Invoice, settings: &InvoiceSettings, template: &str) -> String {
+    let letterhead: String = settings.letterhead.iter().map(|line| format!("<p>{}</p>", escape(line))).collect();
+
+    let mut body = format!("<h1>Invoice {}</h1>\n", escape(&invoice.reference));
+    body += "<table class=\"details\">\n";
+    let mut details = vec![("To", invoice.recipient.clone())];
+    if let Some(email) = &invoice.email {
+        details.push(("Email", email.clone()));
+    }
+    details.extend([
+        ("Invoice date", invoice.date.to_string()),
+        ("Due date", invoice.due_date.to_string()),
+        ("Period", format!("{} to {}", invoice.period.from, invoice.period.to)),
+        ("Reference", invoice.reference.clone()),
+    ]);
+    for (label, value) in details {
+        body += &format!("<tr><th>{}</th><td>{}</td></tr>\n", label, escape(&value));
+    }
+    body += "</table>\n";
+
+    for p in invoice.persons.iter().filter(|p| !p.billable.is_empty()) {
+        body += &format!("<h2>{}</h2>\n", escape(&person_heading(p)));
+        body += "<table class=\"events\">\n<tr><th>Date</th><th>Event</th><th>Class</th><th>Status</th>\
+            <th class=\"amount\">Normal fee</th><th class=\"amount\">Late fee</th></tr>\n";
+        for item in p.billable.iter().flat_map(items) {
+            let event = if item.item.is_empty() { escape(&item.line.display_name()) } else { format!("&nbsp;&nbsp;{}", escape(&item.item)) };
+            body += &format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"amount\">{}</td><td class=\"amount\">{}</td></tr>\n",
+                item.date,
+                event,
+                if item.item.is_empty() { escape(&item.line.class_name) } else { String::new() },
+                escape(&item.status),
+                amount(item.normal_fee),
+                amount(item.late_fee)
+            );
+        }
+        body += "</table>\n";
+    }
+
+    body += "<table class=\"summary\">\n";
+    let summary = summary(invoice);
+    for (index, (label, value)) in summary.iter().enumerate() {
+        let class = if index + 1 == summary.len() { " class=\"total\"" } else { "" };
+        body += &format!("<tr{}><td>{}</td><td class=\"amount\">{}</td></tr>\n", class, label, amount(*value));
+    }
+    body += "</table>\n";
+    body += &format!("<p class=\"payment\">{}</p>\n", escape(&payment_text(invoice, settings)));
+    if let Some((dark, width)) = swish_modules(invoice) {
+        // A quiet zone of four modules around the code, as scanners need.
+        let size = width + 8;
+        let modules: String = dark.iter().map(|(x, y)| format!("M{},{}h1v1h-1z", x + 4, y + 4)).collect();
+        body += &format!(
+            "<div class=\"swish\"><p>Swish</p><svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {0} {0}\" width=\"{1}\" height=\"{1}\" shape-rendering=\"crispEdges\">\
+            <rect width=\"{0}\" height=\"{0}\" fill=\"#fff\"/><path d=\"{2}\" fill=\"#000\"/></svg></div>\n",
+            size, size * 4, modules
+        );
+    }
+
+    template
+        .replace("{{title}}", &escape(&format!("Invoice {}", invoice.reference)))
+        .replace("{{letterhead}}", &letterhead)
+        .replace("{{invoice}}", &body)
+}
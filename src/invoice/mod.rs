@@ -0,0 +1,211 @@
+use crate::billing::Person;
+use crate::dates::{self, DateRange};
+use crate::output::Report;
+use crate::totals::Totals;
+use chrono::{Days, NaiveDate};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+mod html;
+pub mod ocr;
+mod pdf;
+mod swish;
+
+// How invoices look and when they are due, from the `[invoice]` table in the club config.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InvoiceSettings {
+    // Lines at the top of each invoice, the club's name first.
+    #[serde(default)]
+    letterhead: Vec<String>,
+    // An HTML file with {{letterhead}} and {{invoice}} where they go, for the club's own look.
+    template: Option<String>,
+    #[serde(default = "thirty")]
+    due_days: u64,
+    // How to pay, e.g. "Bankgiro 123-4567".
+    payment: Option<String>,
+    // The club's Swish number, for a QR code on each invoice.
+    swish: Option<String>,
+}
+
+fn thirty() -> u64 {
+    30
+}
+
+impl Default for InvoiceSettings {
+    fn default() -> Self {
+        InvoiceSettings { letterhead: vec![], template: None, due_days: thirty(), payment: None, swish: None }
+    }
+}
+
+// What one payer, a member or a household, is asked to pay for the period.
+pub struct Invoice<'a> {
+    pub recipient: String,
+    pub email: Option<String>,
+    pub persons: Vec<&'a Person>,
+    pub totals: Totals,
+    // The member's share if the club has a share policy, or else the total, less what has
+    // already been paid.
+    pub amount: f64,
+    // An OCR reference, which the payer gives with the payment.
+    pub reference: String,
+    pub period: DateRange,
+    pub date: NaiveDate,
+    pub due_date: NaiveDate,
+    // The text of the Swish QR code, when the club has Swish.
+    pub swish: Option<String>,
+}
+
+// An invoice per household when there are payers, or else per person. Those with nothing to pay
+// get no invoice. Two payers whose references are the same are an error, as a payment could not
+// be told apart.
+pub fn invoices<'a>(report: &Report<'a>, period: &DateRange, settings: &InvoiceSettings) -> Result<Vec<Invoice<'a>>, String> {
+    let date = dates::today();
+    let due_date = date.checked_add_days(Days::new(settings.due_days)).unwrap_or(date);
+    let payers: Vec<(String, Option<String>, Vec<&'a Person>, Totals, _)> = if report.households.is_empty() {
+        report
+            .persons
+            .iter()
+            .map(|p| (format!("{} {}", p.person.given, p.person.family), None, vec![p], p.totals, payer(p)))
+            .collect()
+    } else {
+        report
+            .households
+            .iter()
+            .map(|h| {
+                // A listed household is known by its payer's id, as its persons may change.
+                let key = match (h.payer_id, h.persons.first()) {
+                    (Some(id), _) => ocr::Payer::Name(format!("household {}", id)),
+                    (None, Some(person)) => payer(person),
+                    (None, None) => ocr::Payer::Name(h.payer.clone()),
+                };
+                (h.payer.clone(), h.email.clone(), h.persons.clone(), h.totals, key)
+            })
+            .collect()
+    };
+    let invoices: Vec<Invoice<'a>> = payers
+        .into_iter()
+        .map(|(recipient, email, persons, totals, key)| {
+            let reference = ocr::reference(period, &key);
+            let amount = totals.due();
+            Invoice {
+                swish: settings.swish.as_ref().map(|payee| swish::payload(payee, amount, &reference)),
+                reference,
+                recipient,
+                email,
+                persons,
+                totals,
+                amount,
+                period: *period,
+                date,
+                due_date,
+            }
+        })
+        .filter(|invoice| invoice.amount > 0f64)
+        .collect();
+    for (index, invoice) in invoices.iter().enumerate() {
+        if let Some(other) = invoices[..index].iter().find(|other| other.reference == invoice.reference) {
+            return Err(format!(
+                "The invoices to {} and {} have the same reference {}, so payments to them could not be told apart.",
+                other.recipient, invoice.recipient, invoice.reference
+            ));
+        }
+    }
+    Ok(invoices)
+}
+
+// A person is known by the person id, or else by name and birth year.
+fn payer(person: &Person) -> ocr::Payer {
+    match person.person.id {
+        Some(id) => ocr::Payer::Id(id),
+        None => ocr::Payer::Name(format!(
+            "{} {} {}",
+            person.person.given,
+            person.person.family,
+            person.person.birth_year.map(|year| year.to_string()).unwrap_or_default()
+        )),
+    }
+}
+
+// Amounts are written with two decimals.
+fn amount(amount: f64) -> String {
+    format!("{:.2}", amount)
+}
+
+// Writes each invoice as HTML and PDF to `folder`, named by reference and recipient. Returns the
+// number of invoices written.
+pub fn write(invoices: &[Invoice], settings: &InvoiceSettings, folder: &str, verbose: bool) -> Result<usize, String> {
+    let template = match &settings.template {
+        None => html::DEFAULT_TEMPLATE.to_string(),
+        Some(path) => fs::read_to_string(path).map_err(|e| format!("Unable to read invoice template {}: {}", path, e))?,
+    };
+    if !template.contains("{{invoice}}") {
+        return Err("The invoice template has no {{invoice}} for the invoice to go in.".to_string());
+    }
+    fs::create_dir_all(folder).map_err(|e| format!("Unable to create invoice folder {}: {}", folder, e))?;
+    for invoice in invoices.iter() {
+        let name: String = format!("{} {}", invoice.reference, invoice.recipient)
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+            .collect();
+        let path = Path::new(folder).join(&name);
+        let html_path = path.with_extension("html");
+        fs::write(&html_path, html::invoice(invoice, settings, &template))
+            .map_err(|e| format!("Unable to write invoice {:?}: {}", html_path, e))?;
+        let pdf_path = path.with_extension("pdf");
+        fs::write(&pdf_path, pdf::invoice(invoice, settings))
+            .map_err(|e| format!("Unable to write invoice {:?}: {}", pdf_path, e))?;
+        if verbose {
+            eprintln!("Invoice {} to {}: {}", invoice.reference, invoice.recipient, amount(invoice.amount));
+        }
+    }
+    Ok(invoices.len())
+}
+
+// The lines of the summary below the events, as label and amount, the last one being what to pay.
+fn summary(invoice: &Invoice) -> Vec<(&'static str, f64)> {
+    let totals = &invoice.totals;
+    let mut summary = vec![("Normal fees", totals.normal_fees), ("Late fees", totals.late_fees)];
+    for (label, amount) in [("DNS fees", totals.dns_fees), ("Card rental", totals.card_rental), ("Services", totals.services)] {
+        if amount != 0f64 {
+            summary.push((label, amount));
+        }
+    }
+    summary.push(("Total", totals.total));
+    if let Some(club_share) = totals.club_share {
+        summary.push(("Paid by the club", -club_share));
+    }
+    if totals.paid != 0f64 {
+        summary.push(("Already paid", -totals.paid));
+    }
+    summary.push(("To pay", invoice.amount));
+    summary
+}
+
+fn person_heading(p: &Person) -> String {
+    let mut heading = format!("{} {}", p.person.given, p.person.family);
+    match (p.person.id, p.person.birth_year) {
+        (Some(id), Some(year)) => heading += &format!(" ({}, born {})", id, year),
+        (Some(id), None) => heading += &format!(" ({})", id),
+        (None, Some(year)) => heading += &format!(" (born {})", year),
+        (None, None) => {}
+    }
+    heading
+}
+
+fn payment_text(invoice: &Invoice, settings: &InvoiceSettings) -> String {
+    let to = settings.payment.as_ref().map_or(String::new(), |payment| format!(" to {}", payment));
+    let mut text = format!("Please pay {}{} by {}, marked with OCR reference {}.", amount(invoice.amount), to, invoice.due_date, invoice.reference);
+    if invoice.swish.is_some() {
+        text += " You can also pay with Swish by scanning the QR code.";
+    }
+    text
+}
+
+// The Swish QR code's dark modules, as column and row.
+fn swish_modules(invoice: &Invoice) -> Option<(Vec<(usize, usize)>, usize)> {
+    let (modules, width) = swish::qr_code(invoice.swish.as_ref()?)?;
+    let dark = (0..modules.len()).filter(|index| modules[*index]).map(|index| (index % width, index / width)).collect();
+    Some((dark, width))
+}
@@ -0,0 +1,105 @@
+use crate::dates::DateRange;
+use sha2::{Digest, Sha256};
+
+// The check digit of an OCR reference, by the Luhn algorithm (modulus 10) as Bankgirot uses it:
+// every other digit is doubled, starting with the last, and the digits of the products summed.
+pub fn check_digit(digits: &str) -> u32 {
+    let sum: u32 = digits
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(index, digit)| if index % 2 == 0 { digit * 2 } else { digit })
+        .map(|value| value / 10 + value % 10)
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+// Whether `reference` is an OCR reference with a correct check digit. Spaces are allowed, as
+// payers often group the digits.
+pub fn is_valid(reference: &str) -> bool {
+    let digits: String = reference.chars().filter(|c| !c.is_whitespace()).collect();
+    if digits.len() < 2 || digits.len() > 25 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    let (number, check) = digits.split_at(digits.len() - 1);
+    check.chars().next().and_then(|c| c.to_digit(10)) == Some(check_digit(number))
+}
+
+// Who an invoice is to, by what stays the same from one billing to the next.
+pub enum Payer {
+    // A person with a person id.
+    Id(u64),
+    // A person without a person id, by name and birth year, or a household by its payer's id.
+    Name(String),
+}
+
+// The reference for the invoice of a period to a payer, the same every time the period is billed:
+// the first day of the period as YYMMDD, the payer's number and the check digit. The number is the
+// person id, or else a 0, as person ids never start with 0, and nine digits from a hash of the
+// name, in lower case and with single spaces. Two names can give the same digits, which
+// `invoices` reports rather than sending two invoices with the same reference.
+pub fn reference(period: &DateRange, payer: &Payer) -> String {
+    let number = match payer {
+        Payer::Id(id) => format!("{}{}", period.from.format("%y%m%d"), id),
+        Payer::Name(name) => format!("{}0{:09}", period.from.format("%y%m%d"), name_number(name)),
+    };
+    format!("{}{}", number, check_digit(&number))
+}
+
+fn name_number(name: &str) -> u64 {
+    let normalised = name.split_whitespace().collect::<Vec<&str>>().join(" ").to_lowercase();
+    let hash = Sha256::digest(normalised.as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash[..8]);
+    u64::from_be_bytes(bytes) % 1_000_000_000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn period() -> DateRange {
+        DateRange {
+            from: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            to: NaiveDate::from_ymd_opt(2024, 6, 30).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_check_digit() {
+        // Bankgirot's example reference 1234567897.
+        assert_eq!(check_digit("123456789"), 7);
+        assert_eq!(check_digit("0"), 0);
+        assert_eq!(check_digit("1"), 8);
+    }
+
+    #[test]
+    fn test_is_valid() {
+        assert!(is_valid("1234567897"));
+        assert!(is_valid("12345 67897"));
+        assert!(!is_valid("1234567898"));
+        assert!(!is_valid("7"));
+        assert!(!is_valid("12345678901234567890123456"));
+        assert!(!is_valid("12a4567897"));
+    }
+
+    #[test]
+    fn test_reference_by_id() {
+        let reference = reference(&period(), &Payer::Id(12345));
+        assert_eq!(&reference[..11], "24010112345");
+        assert!(is_valid(&reference));
+    }
+
+    #[test]
+    fn test_reference_by_name() {
+        let reference = reference(&period(), &Payer::Name("Anna Berg 2010".to_string()));
+        assert_eq!(reference.len(), 17);
+        assert_eq!(&reference[..7], "2401010");
+        assert!(is_valid(&reference));
+        // The same however the name is spaced or capitalised.
+        assert_eq!(reference, super::reference(&period(), &Payer::Name(" anna  BERG 2010".to_string())));
+        assert_ne!(reference, super::reference(&period(), &Payer::Name("Anna Berg 2011".to_string())));
+    }
+}
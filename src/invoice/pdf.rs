@@ -0,0 +1,251 @@
+use super::{amount, payment_text, person_heading, summary, swish_modules, Invoice, InvoiceSettings};
+use crate::output::items;
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+
+// A4 in points, with the margins used on every page.
+const WIDTH: f32 = 595.0;
+const HEIGHT: f32 = 842.0;
+const LEFT: f32 = 50.0;
+const RIGHT: f32 = 545.0;
+const TOP: f32 = 792.0;
+const BOTTOM: f32 = 60.0;
+
+const REGULAR: Name = Name(b"F1");
+const BOLD: Name = Name(b"F2");
+
+// The standard fonts only have the characters of WinAnsiEncoding, which covers Swedish and most
+// other names in Eventor. Anything else is shown as a question mark.
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '–' => 0x96,
+            '—' => 0x97,
+            c if (c as u32) < 0x80 || (0xA0..=0xFF).contains(&(c as u32)) => c as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+// The width of an amount in Helvetica, regular or bold, for aligning amounts to the right.
+fn amount_width(text: &str, size: f32) -> f32 {
+    text.chars()
+        .map(|c| match c {
+            '0'..='9' => 556.0,
+            '-' => 333.0,
+            _ => 278.0,
+        })
+        .sum::<f32>()
+        * size
+        / 1000.0
+}
+
+// Cuts text that would run into the next column, roughly, as Helvetica averages about half the
+// font size per character.
+fn fit(text: &str, width: f32, size: f32) -> String {
+    let max = (width / (size * 0.5)) as usize;
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        text.chars().take(max.saturating_sub(1)).collect::<String>() + "…"
+    }
+}
+
+// Pages written from the top down, with a new page when one is full.
+struct Pages {
+    pages: Vec<Content>,
+    y: f32,
+}
+
+impl Pages {
+    fn new() -> Pages {
+        Pages { pages: vec![Content::new()], y: TOP }
+    }
+
+    fn content(&mut self) -> &mut Content {
+        self.pages.last_mut().unwrap()
+    }
+
+    // Moves down to the next line, starting a new page if there is no room for it.
+    fn advance(&mut self, height: f32) {
+        self.y -= height;
+        if self.y < BOTTOM {
+            self.pages.push(Content::new());
+            self.y = TOP;
+        }
+    }
+
+    // Starts a new page unless there is room for `height` below the current line.
+    fn reserve(&mut self, height: f32) {
+        if self.y - height < BOTTOM {
+            self.pages.push(Content::new());
+            self.y = TOP;
+        }
+    }
+
+    fn text(&mut self, x: f32, text: &str, font: Name, size: f32) {
+        let y = self.y;
+        let content = self.content();
+        content.begin_text();
+        content.set_font(font, size);
+        content.next_line(x, y);
+        content.show(Str(&win_ansi(text)));
+        content.end_text();
+    }
+
+    // Text wrapped at words to fit `width`, one line after another.
+    fn paragraph(&mut self, x: f32, text: &str, width: f32, font: Name, size: f32) {
+        let mut line = String::new();
+        for word in text.split_whitespace() {
+            if !line.is_empty() && (line.chars().count() + 1 + word.chars().count()) as f32 * size * 0.5 > width {
+                self.text(x, &line, font, size);
+                self.advance(size * 1.3);
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line += word;
+        }
+        self.text(x, &line, font, size);
+    }
+
+    fn amount(&mut self, right: f32, value: f64, font: Name, size: f32) {
+        let text = amount(value);
+        let width = amount_width(&text, size);
+        self.text(right - width, &text, font, size);
+    }
+
+    // The Swish QR code, `size` points wide, with its top left corner at `x` on the current line.
+    fn qr_code(&mut self, x: f32, size: f32, dark: &[(usize, usize)], width: usize) {
+        let module = size / width as f32;
+        let top = self.y;
+        let content = self.content();
+        content.set_fill_gray(0.0);
+        for (column, row) in dark.iter() {
+            content.rect(x + *column as f32 * module, top - (*row as f32 + 1.0) * module, module, module);
+        }
+        content.fill_nonzero();
+    }
+
+    fn rule(&mut self, from: f32, to: f32) {
+        let y = self.y + 9.0;
+        let content = self.content();
+        content.set_line_width(0.5);
+        content.move_to(from, y);
+        content.line_to(to, y);
+        content.stroke();
+    }
+}
+
+pub fn invoice(invoice: &Invoice, settings: &InvoiceSettings) -> Vec<u8> {
+    let mut pages = Pages::new();
+
+    for (index, line) in settings.letterhead.iter().enumerate() {
+        if index == 0 {
+            pages.text(LEFT, line, BOLD, 14.0);
+            pages.advance(16.0);
+        } else {
+            pages.text(LEFT, line, REGULAR, 10.0);
+            pages.advance(12.0);
+        }
+    }
+    if !settings.letterhead.is_empty() {
+        pages.rule(LEFT, RIGHT);
+        pages.advance(20.0);
+    }
+
+    pages.text(LEFT, &format!("Invoice {}", invoice.reference), BOLD, 16.0);
+    pages.advance(24.0);
+    let mut details = vec![("To", invoice.recipient.clone())];
+    if let Some(email) = &invoice.email {
+        details.push(("Email", email.clone()));
+    }
+    details.extend([
+        ("Invoice date", invoice.date.to_string()),
+        ("Due date", invoice.due_date.to_string()),
+        ("Period", format!("{} to {}", invoice.period.from, invoice.period.to)),
+        ("Reference", invoice.reference.clone()),
+    ]);
+    for (label, value) in details {
+        pages.text(LEFT, label, BOLD, 10.0);
+        pages.text(LEFT + 90.0, &value, REGULAR, 10.0);
+        pages.advance(13.0);
+    }
+
+    for p in invoice.persons.iter().filter(|p| !p.billable.is_empty()) {
+        pages.advance(14.0);
+        pages.text(LEFT, &person_heading(p), BOLD, 12.0);
+        pages.advance(16.0);
+        for (x, header) in [(LEFT, "Date"), (110.0, "Event"), (340.0, "Class"), (395.0, "Status")] {
+            pages.text(x, header, BOLD, 9.0);
+        }
+        for (right, header) in [(480.0, "Normal fee"), (RIGHT, "Late fee")] {
+            // Headers are roughly right-aligned, as their widths are not known exactly.
+            pages.text(right - header.len() as f32 * 4.6, header, BOLD, 9.0);
+        }
+        pages.advance(12.0);
+        for item in p.billable.iter().flat_map(items) {
+            let event = if item.item.is_empty() { item.line.display_name() } else { format!("   {}", item.item) };
+            pages.text(LEFT, &item.date.to_string(), REGULAR, 9.0);
+            pages.text(110.0, &fit(&event, 225.0, 9.0), REGULAR, 9.0);
+            if item.item.is_empty() {
+                pages.text(340.0, &fit(&item.line.class_name, 50.0, 9.0), REGULAR, 9.0);
+            }
+            pages.text(395.0, &item.status, REGULAR, 9.0);
+            pages.amount(480.0, item.normal_fee, REGULAR, 9.0);
+            pages.amount(RIGHT, item.late_fee, REGULAR, 9.0);
+            pages.advance(12.0);
+        }
+    }
+
+    pages.advance(14.0);
+    let summary = summary(invoice);
+    for (index, (label, value)) in summary.iter().enumerate() {
+        let font = if index + 1 == summary.len() { BOLD } else { REGULAR };
+        if index + 1 == summary.len() {
+            pages.rule(340.0, RIGHT);
+        }
+        pages.text(340.0, label, font, 10.0);
+        pages.amount(RIGHT, *value, font, 10.0);
+        pages.advance(13.0);
+    }
+    pages.advance(14.0);
+    pages.paragraph(LEFT, &payment_text(invoice, settings), RIGHT - LEFT, REGULAR, 10.0);
+    if let Some((dark, width)) = swish_modules(invoice) {
+        let size = 110.0;
+        pages.advance(24.0);
+        pages.reserve(size + 16.0);
+        pages.text(LEFT, "Swish", BOLD, 10.0);
+        pages.advance(6.0);
+        pages.qr_code(LEFT, size, &dark, width);
+    }
+
+    let mut pdf = Pdf::new();
+    let catalog = Ref::new(1);
+    let tree = Ref::new(2);
+    let regular = Ref::new(3);
+    let bold = Ref::new(4);
+    let page_ids: Vec<(Ref, Ref)> = (0..pages.pages.len() as i32)
+        .map(|index| (Ref::new(5 + 2 * index), Ref::new(6 + 2 * index)))
+        .collect();
+    pdf.catalog(catalog).pages(tree);
+    pdf.pages(tree).kids(page_ids.iter().map(|(page, _)| *page)).count(page_ids.len() as i32);
+    for ((page_id, content_id), content) in page_ids.into_iter().zip(pages.pages) {
+        let mut page = pdf.page(page_id);
+        page.media_box(Rect::new(0.0, 0.0, WIDTH, HEIGHT));
+        page.parent(tree);
+        page.contents(content_id);
+        page.resources().fonts().pair(REGULAR, regular).pair(BOLD, bold);
+        page.finish();
+        pdf.stream(content_id, &content.finish());
+    }
+    pdf.type1_font(regular).base_font(Name(b"Helvetica")).encoding_predefined(Name(b"WinAnsiEncoding"));
+    pdf.type1_font(bold).base_font(Name(b"Helvetica-Bold")).encoding_predefined(Name(b"WinAnsiEncoding"));
+    pdf.finish()
+}
@@ -0,0 +1,23 @@
+use qrcode::{Color, EcLevel, QrCode};
+
+// Swish allows messages of at most 50 characters.
+const MAX_MESSAGE: usize = 50;
+
+// Fields that the payer can't change in the Swish app, summed: 1 for the payee, 2 for the amount
+// and 4 for the message.
+const LOCKED: u32 = 7;
+
+// The text of a prefilled Swish QR code, in the unencrypted format that the Swish app reads:
+// "C" followed by the payee, amount, message and locked fields, separated by semicolons.
+pub fn payload(payee: &str, amount: f64, message: &str) -> String {
+    let payee: String = payee.chars().filter(|c| c.is_ascii_digit()).collect();
+    let message: String = message.chars().filter(|c| *c != ';').take(MAX_MESSAGE).collect();
+    format!("C{};{};{};{}", payee, format!("{:.2}", amount).replace('.', ","), message, LOCKED)
+}
+
+// The QR code's modules, row by row, true for dark, and the number of modules per row.
+pub fn qr_code(payload: &str) -> Option<(Vec<bool>, usize)> {
+    let code = QrCode::with_error_correction_level(payload, EcLevel::M).ok()?;
+    let modules = code.to_colors().into_iter().map(|color| color == Color::Dark).collect();
+    Some((modules, code.width()))
+}
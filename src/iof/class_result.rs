@@ -1,28 +1,35 @@
-use crate::iof::{numeric_contents, subelements, ClassResult, IOFXMLError};
+use crate::iof::{xml, ClassResult, IOFXMLError};
 use std::convert::TryFrom;
-use xmltree::Element;
 
-impl TryFrom<&Element> for ClassResult {
+impl TryFrom<&xml::ClassResult> for ClassResult {
     type Error = IOFXMLError;
 
-    fn try_from(element: &Element) -> Result<Self, Self::Error> {
-        let event_class_element = element
-            .get_child("EventClass")
-            .ok_or("No event class was specified for class result.")?;
-        let event_class_id = numeric_contents(event_class_element, "EventClassId")
-            .ok_or("Missing event class id in class result.")?;
-        let class_race_info_element = event_class_element
-            .get_child("ClassRaceInfo")
-            .ok_or("Event class is missing class race info")?;
-        let event_race_id = numeric_contents(class_race_info_element, "EventRaceId")
+    fn try_from(class_result: &xml::ClassResult) -> Result<Self, Self::Error> {
+        let event_class = &class_result.event_class;
+        let event_race_id = event_class
+            .class_race_info
+            .as_ref()
+            .ok_or("Event class is missing class race info")?
+            .event_race_id
             .ok_or("Class race info is missing the event race id (or it is malformed)")?;
 
-        let person_results = subelements(element, "PersonResult")?;
+        let person_results = class_result
+            .person_results
+            .iter()
+            .map(|person_result| person_result.try_into())
+            .collect::<Result<_, _>>()?;
+        let team_results = class_result
+            .team_results
+            .iter()
+            .map(|team_result| team_result.try_into())
+            .collect::<Result<_, _>>()?;
 
         Ok(ClassResult {
-            event_class_id,
+            event_class_id: event_class.event_class_id,
+            class_name: event_class.class_short_name.clone().or(event_class.name.clone()),
             event_race_id,
             person_results,
+            team_results,
         })
     }
 }
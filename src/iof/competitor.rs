@@ -1,43 +1,24 @@
-use crate::iof::{numeric_contents, textual_contents, Competitor, IOFXMLError};
+use crate::iof::{xml, year_of_date, Competitor, IOFXMLError};
 use std::convert::TryFrom;
-use xmltree::Element;
 
-impl TryFrom<&Element> for Competitor {
+impl TryFrom<&xml::Person> for Competitor {
     type Error = IOFXMLError;
 
-    fn try_from(element: &Element) -> Result<Self, Self::Error> {
-        let id = numeric_contents(element, "PersonId");
-        let name_element = element
-            .get_child("PersonName")
-            .ok_or("No name given for person")?;
-        let family = textual_contents(name_element, "Family")
-            .ok_or("No family name specified for competitor.")?;
-        let given = textual_contents(name_element, "Given")
-            .ok_or("No given name specified for competitor.")?;
-        let mut birth_year: Option<u64> = None;
-        if let Some(birth_year_element) = element.get_child("BirthDate") {
-            birth_year = textual_contents(birth_year_element, "Date")
-                .map(|d| {
-                    if d.len() > 4 {
-                        d[0..4].parse::<u64>().ok()
-                    } else {
-                        None
-                    }
-                })
-                .flatten();
-        }
+    fn try_from(person: &xml::Person) -> Result<Self, Self::Error> {
+        let name = person.person_name.as_ref().ok_or("No name given for person")?;
+        let mut given: Vec<&xml::Given> = name.given.iter().collect();
+        given.sort_by_key(|given| given.sequence.as_ref().and_then(|sequence| sequence.trim().parse::<u64>().ok()));
+        let given: Vec<&str> = given.iter().map(|given| given.name.trim()).collect();
+        let birth_year = person
+            .birth_date
+            .as_ref()
+            .and_then(|birth_date| year_of_date(&birth_date.date));
 
         Ok(Competitor {
-            id,
-            given,
-            family,
+            id: person.person_id,
+            given: given.join(" "),
+            family: name.family.clone(),
             birth_year,
         })
     }
 }
-
-impl Competitor {
-    pub fn probably_the_same_as(&self, other: &Competitor) -> bool {
-        self.given == other.given && self.family == other.family
-    }
-}
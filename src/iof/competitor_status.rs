@@ -0,0 +1,68 @@
+use crate::iof::CompetitorStatus;
+use std::fmt;
+use std::str::FromStr;
+
+impl FromStr for CompetitorStatus {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Accepts the values used in result lists of both IOF XML versions and the abbreviations we print, so that
+        // statuses can be given on the command line in either form.
+        match s {
+            // IOF XML 3.0 has DidNotEnter for entries that were never made.
+            "Inactive" | "DidNotEnter" => Ok(CompetitorStatus::Inactive),
+            "Active" => Ok(CompetitorStatus::Active),
+            "Finished" => Ok(CompetitorStatus::Finished),
+            "OK" => Ok(CompetitorStatus::OK),
+            "DidNotStart" | "DNS" => Ok(CompetitorStatus::DidNotStart),
+            "DidNotFinish" | "DNF" => Ok(CompetitorStatus::DidNotFinish),
+            "MisPunch" | "MissingPunch" | "MP" => Ok(CompetitorStatus::MisPunch),
+            "Disqualified" | "DSQ" => Ok(CompetitorStatus::Disqualified),
+            "NotCompeting" | "NC" => Ok(CompetitorStatus::NotCompeting),
+            "SportWithdr" | "SportingWithdrawal" | "SW" => Ok(CompetitorStatus::SportingWithdrawal),
+            "OverTime" | "OT" => Ok(CompetitorStatus::OverTime),
+            "Moved" => Ok(CompetitorStatus::Moved),
+            "MovedUp" => Ok(CompetitorStatus::MovedUp),
+            "Cancelled" => Ok(CompetitorStatus::Cancelled),
+            "Vacant" => Ok(CompetitorStatus::Vacant),
+            _ => Err("Unrecognized competitor status."),
+        }
+    }
+}
+
+impl fmt::Display for CompetitorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let abbreviation = match self {
+            CompetitorStatus::Inactive => "Inactive",
+            CompetitorStatus::Active => "Active",
+            CompetitorStatus::Finished => "Finished",
+            CompetitorStatus::OK => "OK",
+            CompetitorStatus::DidNotStart => "DNS",
+            CompetitorStatus::DidNotFinish => "DNF",
+            CompetitorStatus::MisPunch => "MP",
+            CompetitorStatus::Disqualified => "DSQ",
+            CompetitorStatus::NotCompeting => "NC",
+            CompetitorStatus::SportingWithdrawal => "SW",
+            CompetitorStatus::OverTime => "OT",
+            CompetitorStatus::Moved => "Moved",
+            CompetitorStatus::MovedUp => "MovedUp",
+            CompetitorStatus::Cancelled => "Cancelled",
+            CompetitorStatus::Vacant => "Vacant",
+            CompetitorStatus::Unknown => "?",
+        };
+        write!(f, "{}", abbreviation)
+    }
+}
+
+impl CompetitorStatus {
+    // Statuses where the competitor never made it to the start, for whatever reason.
+    pub fn started(&self) -> bool {
+        !matches!(
+            self,
+            CompetitorStatus::Inactive
+                | CompetitorStatus::DidNotStart
+                | CompetitorStatus::Cancelled
+                | CompetitorStatus::Vacant
+        )
+    }
+}
@@ -0,0 +1,41 @@
+use crate::iof::Discipline;
+use std::fmt;
+use std::str::FromStr;
+
+impl Discipline {
+    pub fn from_id(id: u64) -> Option<Self> {
+        match id {
+            1 => Some(Discipline::Foot),
+            2 => Some(Discipline::MountainBike),
+            3 => Some(Discipline::Ski),
+            4 => Some(Discipline::Trail),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Discipline {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "foot" => Ok(Discipline::Foot),
+            "mtb" => Ok(Discipline::MountainBike),
+            "ski" => Ok(Discipline::Ski),
+            "trail" => Ok(Discipline::Trail),
+            _ => Err("Unrecognized discipline, should be one of foot, mtb, ski or trail."),
+        }
+    }
+}
+
+impl fmt::Display for Discipline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Discipline::Foot => "foot",
+            Discipline::MountainBike => "mtb",
+            Discipline::Ski => "ski",
+            Discipline::Trail => "trail",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -0,0 +1,202 @@
+use crate::iof::v3;
+use crate::iof::{xml, IOFXMLError, IofVersion};
+use crate::iof::{Competitor, EntryFee, Event, EventClass, ServiceRequest};
+use quick_xml::events::Event as XmlEvent;
+use quick_xml::Reader;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use xmltree::Element;
+
+// Parsing of whole documents, in either IOF XML version. The version is detected from the root
+// element, so the same functions work for Eventor responses and for files from other programs.
+// IOF XML 2.0.3 is read through the serde mapping in `xml`, IOF XML 3.0 as an element tree. A
+// document read from a file is not kept in memory, but read again from the file each time a list
+// is taken from it. Result and entry lists are streamed by the functions in `stream`, while the
+// other lists, which are small, are read whole.
+pub struct Document {
+    content: Content,
+    root: String,
+    version: IofVersion,
+}
+
+enum Content {
+    // Documents made by tkassa itself, such as an event list of local files.
+    Text(String),
+    File(PathBuf),
+}
+
+// The name of the root element and the IOF XML version given on it.
+fn root<R: BufRead>(reader: &mut Reader<R>) -> Result<(String, IofVersion), IOFXMLError> {
+    let mut buffer = vec![];
+    loop {
+        match reader.read_event_into(&mut buffer) {
+            Ok(XmlEvent::Start(start)) | Ok(XmlEvent::Empty(start)) => {
+                let root = String::from_utf8_lossy(start.name().as_ref()).to_string();
+                let version = match start.try_get_attribute("iofVersion") {
+                    Ok(Some(version)) if version.value.starts_with(b"3") => IofVersion::V3_0,
+                    _ => IofVersion::V2_0_3,
+                };
+                return Ok((root, version));
+            }
+            Ok(XmlEvent::Eof) => return Err("The XML document is empty."),
+            Err(_) => return Err("The XML document is malformed."),
+            _ => {}
+        }
+        buffer.clear();
+    }
+}
+
+impl Document {
+    pub fn parse(text: String) -> Result<Document, IOFXMLError> {
+        let (root, version) = root(&mut Reader::from_str(&text))?;
+        Ok(Document { content: Content::Text(text), root, version })
+    }
+
+    // Only the root element is read here, the rest when a list is taken from the document.
+    pub fn open(path: &Path) -> Result<Document, IOFXMLError> {
+        let file = File::open(path).map_err(|_| "The XML file could not be read.")?;
+        let (root, version) = root(&mut Reader::from_reader(BufReader::new(file)))?;
+        Ok(Document { content: Content::File(path.to_path_buf()), root, version })
+    }
+
+    pub fn version(&self) -> IofVersion {
+        self.version
+    }
+
+    fn input(&self) -> Result<Box<dyn BufRead + '_>, IOFXMLError> {
+        match &self.content {
+            Content::Text(text) => Ok(Box::new(text.as_bytes())),
+            Content::File(path) => {
+                let file = File::open(path).map_err(|_| "The XML file could not be read.")?;
+                Ok(Box::new(BufReader::new(file)))
+            }
+        }
+    }
+
+    // A reader of the document from the start.
+    pub fn reader(&self) -> Result<Reader<Box<dyn BufRead + '_>>, IOFXMLError> {
+        Ok(Reader::from_reader(self.input()?))
+    }
+
+    // Whether there is any element with the given name, without parsing the whole document.
+    pub fn has_element(&self, name: &str) -> bool {
+        let Ok(mut reader) = self.reader() else {
+            return false;
+        };
+        let mut buffer = vec![];
+        loop {
+            match reader.read_event_into(&mut buffer) {
+                Ok(XmlEvent::Start(start)) | Ok(XmlEvent::Empty(start))
+                    if start.name().as_ref() == name.as_bytes() => return true,
+                Ok(XmlEvent::Eof) | Err(_) => return false,
+                _ => {}
+            }
+            buffer.clear();
+        }
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, IOFXMLError> {
+        quick_xml::de::from_reader(self.input()?)
+            .map_err(|_| "The XML document does not follow IOF XML 2.0.3.")
+    }
+
+    fn element(&self) -> Result<Element, IOFXMLError> {
+        Element::parse(self.input()?).map_err(|_| "The XML document is malformed.")
+    }
+}
+
+fn converted<'a, X: 'a, T: TryFrom<&'a X, Error = IOFXMLError>>(
+    items: &'a [X],
+) -> Result<Vec<T>, IOFXMLError> {
+    items.iter().map(|item| item.try_into()).collect()
+}
+
+impl FromStr for IofVersion {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "2.0.3" | "2" => Ok(IofVersion::V2_0_3),
+            "3.0" | "3" => Ok(IofVersion::V3_0),
+            _ => Err("Unrecognized IOF XML version, should be 2.0.3 or 3.0."),
+        }
+    }
+}
+
+impl fmt::Display for IofVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IofVersion::V2_0_3 => write!(f, "2.0.3"),
+            IofVersion::V3_0 => write!(f, "3.0"),
+        }
+    }
+}
+
+// An event list, or a single event.
+pub fn events(document: &Document) -> Result<Vec<Event>, IOFXMLError> {
+    if document.root == "Event" {
+        return Ok(vec![event(document)?]);
+    }
+    match document.version {
+        IofVersion::V2_0_3 => converted(&document.deserialize::<xml::EventList>()?.events),
+        IofVersion::V3_0 => v3::subelements(&document.element()?, "Event"),
+    }
+}
+
+pub fn event(document: &Document) -> Result<Event, IOFXMLError> {
+    match document.version {
+        IofVersion::V2_0_3 => (&document.deserialize::<xml::Event>()?).try_into(),
+        IofVersion::V3_0 => v3::V3(&document.element()?).try_into(),
+    }
+}
+
+pub fn entry_fees(document: &Document) -> Result<Vec<EntryFee>, IOFXMLError> {
+    match document.version {
+        IofVersion::V2_0_3 => converted(&document.deserialize::<xml::EntryFeeList>()?.entry_fees),
+        IofVersion::V3_0 => v3::entry_fees(&document.element()?),
+    }
+}
+
+pub fn event_classes(document: &Document) -> Result<Vec<EventClass>, IOFXMLError> {
+    match document.version {
+        IofVersion::V2_0_3 => converted(&document.deserialize::<xml::EventClassList>()?.event_classes),
+        IofVersion::V3_0 => v3::subelements(&document.element()?, "Class"),
+    }
+}
+
+pub fn persons(document: &Document) -> Result<Vec<Competitor>, IOFXMLError> {
+    match document.version {
+        IofVersion::V2_0_3 => converted(&document.deserialize::<xml::PersonList>()?.persons),
+        IofVersion::V3_0 => v3::subelements(&document.element()?, "Person"),
+    }
+}
+
+// Person service requests are either listed directly, or grouped by organisation.
+pub fn service_requests(document: &Document) -> Result<Vec<ServiceRequest>, IOFXMLError> {
+    match document.version {
+        IofVersion::V2_0_3 => {
+            let list = document.deserialize::<xml::ServiceRequestList>()?;
+            let mut requests = converted(&list.person_service_requests)?;
+            for organisation in list.organisation_service_requests.iter() {
+                requests.append(&mut converted(&organisation.person_service_requests)?);
+            }
+            Ok(requests)
+        }
+        IofVersion::V3_0 => {
+            let root = document.element()?;
+            let mut requests = v3::subelements(&root, "PersonServiceRequest")?;
+            for node in root.children.iter() {
+                if let xmltree::XMLNode::Element(element) = node {
+                    if element.name == "OrganisationServiceRequest" {
+                        requests.append(&mut v3::subelements(element, "PersonServiceRequest")?);
+                    }
+                }
+            }
+            Ok(requests)
+        }
+    }
+}
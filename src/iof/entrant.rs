@@ -1,18 +1,36 @@
-use crate::iof::Entrant;
-use crate::iof::{numeric_contents, IOFXMLError};
+use crate::iof::{xml, Entrant, IOFXMLError, TeamMember};
 use std::convert::TryFrom;
-use xmltree::Element;
 
-impl TryFrom<&Element> for Entrant {
+impl From<&xml::TeamCompetitor> for TeamMember {
+    fn from(competitor: &xml::TeamCompetitor) -> Self {
+        // The person id is either given directly or as part of a full person element.
+        let person_id = competitor.person_id.or_else(|| {
+            competitor
+                .person
+                .as_ref()
+                .and_then(|person| person.person_id)
+        });
+
+        TeamMember { person_id, leg: competitor.team_sequence }
+    }
+}
+
+impl TryFrom<&xml::Entry> for Entrant {
     type Error = IOFXMLError;
 
-    fn try_from(element: &Element) -> Result<Self, Self::Error> {
-        match element.get_child("Competitor") {
-            Some(element) => Ok(match numeric_contents(element, "PersonId") {
+    fn try_from(entry: &xml::Entry) -> Result<Self, Self::Error> {
+        match &entry.competitor {
+            Some(competitor) => Ok(match competitor.person_id {
                 Some(id) => Entrant::Individual(id),
                 None => Entrant::Unknown,
             }),
-            None => Ok(Entrant::Team),
+            None => {
+                let name = entry.team_name.clone().unwrap_or_default();
+                let mut members: Vec<TeamMember> =
+                    entry.team_competitors.iter().map(TeamMember::from).collect();
+                members.sort_by_key(|m| m.leg);
+                Ok(Entrant::Team { name, members })
+            }
         }
     }
 }
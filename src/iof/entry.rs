@@ -1,36 +1,54 @@
-use crate::iof::{subelements, IOFXMLError};
-use crate::iof::{ClassEntryFee, Entrant, Entry, EntryFee};
+use crate::iof::{xml, CCard, ClassEntryFee, Entrant, Entry, IOFXMLError};
 use std::convert::TryFrom;
-use xmltree::Element;
 
-impl TryFrom<&Element> for Entry {
+impl TryFrom<&xml::Entry> for Entry {
     type Error = IOFXMLError;
 
-    fn try_from(element: &Element) -> Result<Self, Self::Error> {
-        let entrant = element.try_into()?;
-        let mut fees: Vec<ClassEntryFee> = subelements(element, "EntryEntryFee")?;
-        fees.sort_by(|a, b| a.sequence.cmp(&b.sequence));
+    fn try_from(entry: &xml::Entry) -> Result<Self, Self::Error> {
+        let entrant = entry.try_into()?;
+        let mut fees: Vec<ClassEntryFee> = entry.entry_fees.iter().map(ClassEntryFee::from).collect();
+        fees.sort_by_key(|f| f.sequence);
+
+        let event_class_id = entry
+            .entry_class
+            .as_ref()
+            .and_then(|entry_class| entry_class.event_class_id);
+        let card = entry
+            .competitor
+            .as_ref()
+            .and_then(|competitor| competitor.cards.first())
+            .map(|card| CCard { id: card.c_card_id.clone() });
 
         Ok(Entry {
+            id: entry.entry_id,
             entrant,
             fee_ids: fees.into_iter().map(|f| f.id).collect(),
+            event_class_id,
+            card,
         })
     }
 }
 
 impl Entry {
+    // A team entry is for the team that has any of the given persons as a member. Teams are
+    // often entered before the runners are known, and are then recognized by the team name.
+    pub fn is_for_team(&self, team_name: &str, person_ids: &[u64]) -> bool {
+        match &self.entrant {
+            Entrant::Team { name, members } => {
+                members
+                    .iter()
+                    .any(|m| m.person_id.is_some_and(|id| person_ids.contains(&id)))
+                    || (members.iter().all(|m| m.person_id.is_none()) && name == team_name)
+            }
+            _ => false,
+        }
+    }
+
     pub fn is_for_person(&self, person_id: &Option<u64>) -> bool {
         if let Some(person_id) = person_id {
-            match self.entrant {
-                Entrant::Individual(id) if id == *person_id => true,
-                _ => false,
-            }
+            matches!(self.entrant, Entrant::Individual(id) if id == *person_id)
         } else {
             false
         }
     }
-
-    pub fn paid_fees(&self, entry_fees: &Vec<EntryFee>) -> (f64, f64) {
-        EntryFee::paid_fees_from_fee_ids(&self.fee_ids, entry_fees)
-    }
 }
@@ -0,0 +1,19 @@
+use crate::iof::{xml, EntryBreak, IOFXMLError};
+use std::convert::TryFrom;
+
+impl TryFrom<&xml::EntryBreak> for EntryBreak {
+    type Error = IOFXMLError;
+
+    fn try_from(entry_break: &xml::EntryBreak) -> Result<Self, Self::Error> {
+        let valid_from = match &entry_break.valid_from_date {
+            Some(date) => Some(date.try_into()?),
+            None => None,
+        };
+        let valid_to = match &entry_break.valid_to_date {
+            Some(date) => Some(date.try_into()?),
+            None => None,
+        };
+
+        Ok(EntryBreak { valid_from, valid_to })
+    }
+}
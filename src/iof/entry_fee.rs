@@ -1,70 +1,78 @@
-use crate::iof::{numeric_contents, textual_contents, year_from_date_string, IOFXMLError};
-use crate::iof::{EntryFee, ValueOperator};
+use crate::iof::{xml, year_of_date, EntryFee, IOFXMLError, ValueOperator};
 use std::convert::TryFrom;
-use xmltree::Element;
 
-impl TryFrom<&Element> for EntryFee {
+impl TryFrom<&xml::EntryFee> for EntryFee {
     type Error = IOFXMLError;
 
-    fn try_from(element: &Element) -> Result<Self, Self::Error> {
-        let id: u64 =
-            numeric_contents(element, "EntryFeeId").ok_or("Entry fee id missing or malformed!")?;
-        let name = textual_contents(element, "Name").ok_or("Entry fee name missing!")?;
-        let amount: f64 =
-            numeric_contents(element, "Amount").ok_or("Entry fee amount missing or malformed!")?;
-        let operator = match element.attributes.get("valueOperator") {
-            Some(value) => match value.as_str() {
-                "fixed" => Ok(ValueOperator::Fixed),
-                "percent" => Ok(ValueOperator::Percent),
-                _ => Err("Unrecognized value operator for entry fee."),
-            },
-            None => Err("No value operator specified for entry fee."),
+    fn try_from(fee: &xml::EntryFee) -> Result<Self, Self::Error> {
+        let operator = match fee.value_operator.as_str() {
+            "fixed" => Ok(ValueOperator::Fixed),
+            "percent" => Ok(ValueOperator::Percent),
+            _ => Err("Unrecognized value operator for entry fee."),
         }?;
-
-        let from_year_of_birth: Option<u64> =
-            if let Some(from_date_of_birth_element) = element.get_child("FromDateOfBirth") {
-                if let Some(text) = textual_contents(from_date_of_birth_element, "Date") {
-                    year_from_date_string(&text)
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
-        let to_year_of_birth: Option<u64> =
-            if let Some(to_date_of_birth_element) = element.get_child("ToDateOfBirth") {
-                if let Some(text) = textual_contents(to_date_of_birth_element, "Date") {
-                    year_from_date_string(&text)
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
+        let year_of_birth = |date: &Option<xml::Time>| -> Option<u64> {
+            date.as_ref().and_then(|date| year_of_date(&date.date))
+        };
 
         Ok(EntryFee {
-            id,
-            name,
-            amount,
+            id: fee.entry_fee_id,
+            name: fee.name.clone(),
+            amount: fee.amount.amount,
             operator,
-            from_year_of_birth,
-            to_year_of_birth,
+            from_year_of_birth: year_of_birth(&fee.from_date_of_birth),
+            to_year_of_birth: year_of_birth(&fee.to_date_of_birth),
+            event_race_id: fee.event_race_id,
+            card_rental: EntryFee::looks_like_card_rental(&fee.name),
         })
     }
 }
 
 impl EntryFee {
+    // Organisers sometimes refer to fees they have since removed, so a fee id may not be found.
+    pub fn find<'a>(fee_id: &u64, event_fees: &'a [EntryFee]) -> Option<&'a EntryFee> {
+        event_fees.iter().find(|event_fee| event_fee.id == *fee_id)
+    }
+
+    // The fee ids that are not among the fees of the event.
+    pub fn undefined(fee_ids: &[u64], event_fees: &[EntryFee]) -> Vec<u64> {
+        fee_ids
+            .iter()
+            .filter(|fee_id| EntryFee::find(fee_id, event_fees).is_none())
+            .copied()
+            .collect()
+    }
+
+    // Whether the fee applies to someone born the given year.
+    pub fn applies_to_birth_year(&self, birth_year: &u64) -> bool {
+        match (self.from_year_of_birth, self.to_year_of_birth) {
+            (Some(from_year), _) if birth_year < &from_year => false,
+            (_, Some(to_year)) if birth_year > &to_year => false,
+            _ => true,
+        }
+    }
+
+    // Eventor has no specific fee type for card rental, so this is a guess from the name organisers
+    // usually give it, e.g. "Hyrbricka", "Hyrpinne" or "Rental card". A fee named otherwise is
+    // billed as an entry fee, and "Hyra av tält" would be taken for card rental. Clubs can list the
+    // names used instead, with `card_rental_fees` in the config file.
+    pub fn looks_like_card_rental(name: &str) -> bool {
+        let name = name.to_lowercase();
+        name.starts_with("hyr") || name.contains(" hyr") || name.contains("rental")
+    }
+
+    pub fn is_card_rental(&self) -> bool {
+        self.card_rental
+    }
+
+    // Fee ids not among the fees of the event are left out.
     pub fn paid_fees_from_fee_ids(
-        applicable_fee_ids: &Vec<u64>,
-        event_fees: &Vec<EntryFee>,
+        applicable_fee_ids: &[u64],
+        event_fees: &[EntryFee],
     ) -> (f64, f64) {
         applicable_fee_ids
             .iter()
-            .fold((0f64, 0f64), |acc, fee_id| -> (f64, f64) {
-                let fee = event_fees
-                    .iter()
-                    .find(|event_fee| event_fee.id == *fee_id)
-                    .expect("Event fee id not found!");
+            .filter_map(|fee_id| EntryFee::find(fee_id, event_fees))
+            .fold((0f64, 0f64), |acc, fee| -> (f64, f64) {
                 // This code makes some assumptions on how fee types are usually applied, since the division
                 // between a normal fee and a late fee is not present in the Eventor data model.
                 match fee.operator {
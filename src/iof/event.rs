@@ -1,32 +1,85 @@
-use crate::iof::{numeric_contents, subelements, textual_contents, IOFXMLError};
-use crate::iof::{Event, EventorTime};
+use crate::iof::{xml, IOFXMLError};
+use crate::iof::{Discipline, Event, EventClassification, EventStatus, EventorTime, Race};
+use chrono::NaiveDate;
 use std::convert::TryFrom;
-use xmltree::Element;
 
-impl TryFrom<&Element> for Event {
+impl TryFrom<&xml::Event> for Event {
     type Error = IOFXMLError;
 
-    fn try_from(element: &Element) -> Result<Self, Self::Error> {
-        let id: u64 =
-            numeric_contents(element, "EventId").ok_or("Event id missing or malformed!")?;
-        let name = textual_contents(element, "Name").ok_or("Event name missing!")?;
-        let races = subelements(element, "EventRace")?;
+    fn try_from(event: &xml::Event) -> Result<Self, Self::Error> {
+        let races = event
+            .event_races
+            .iter()
+            .map(|race| race.try_into())
+            .collect::<Result<Vec<Race>, _>>()?;
+        let classification = event
+            .event_classification_id
+            .and_then(EventClassification::from_id);
+        let discipline = event.discipline_id.and_then(Discipline::from_id);
+        let status = event.event_status_id.and_then(EventStatus::from_id);
+        // The organisers are listed either by id only, or with the full organisation.
+        let organiser_ids = match &event.organiser {
+            Some(organiser) => organiser
+                .organisation_ids
+                .iter()
+                .copied()
+                .chain(organiser.organisations.iter().filter_map(|o| o.organisation_id))
+                .collect(),
+            None => vec![],
+        };
+
+        let entry_breaks = event
+            .entry_breaks
+            .iter()
+            .map(|entry_break| entry_break.try_into())
+            .collect::<Result<_, _>>()?;
+        let modify_date = match &event.modify_date {
+            Some(modify_date) => Some(modify_date.try_into()?),
+            None => None,
+        };
 
-        Ok(Event { id, name, races })
+        Ok(Event {
+            id: event.event_id,
+            name: event.name.clone(),
+            classification,
+            discipline,
+            status,
+            organiser_ids,
+            entry_breaks,
+            web_url: event.web_url.clone(),
+            modify_date,
+            races,
+        })
     }
 }
 
 impl Event {
-    pub fn first_race_date(&self) -> u64 {
-        self.races.first().map(|race| race.date.date).unwrap_or(0)
+    pub fn first_race_date(&self) -> Option<NaiveDate> {
+        self.races.first().map(|race| race.date.date)
     }
 
-    pub fn date_for_race(&self, event_race_id: &u64) -> EventorTime {
+    pub fn is_cancelled(&self) -> bool {
+        self.status == Some(EventStatus::Cancelled)
+    }
+
+    pub fn is_multi_day(&self) -> bool {
+        self.races.len() > 1
+    }
+
+    // Stages are numbered from 1, in the order Eventor lists them.
+    pub fn stage_number(&self, event_race_id: &u64) -> Option<usize> {
         self.races
             .iter()
-            .find(|race| race.id == *event_race_id)
-            .expect("Unknown race id!")
-            .date
-            .clone()
+            .position(|race| race.id == *event_race_id)
+            .map(|index| index + 1)
+    }
+
+    pub fn race(&self, event_race_id: &u64) -> Option<&Race> {
+        self.races.iter().find(|race| race.id == *event_race_id)
+    }
+
+    // None for a race id that the event does not have, e.g. from an inconsistent result list.
+    pub fn date_for_race(&self, event_race_id: &u64) -> Option<EventorTime> {
+        self.race(event_race_id).map(|race| race.date)
     }
 }
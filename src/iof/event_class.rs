@@ -1,21 +1,24 @@
-use crate::iof::{numeric_contents, textual_contents, subelements, ClassEntryFee, EntryFee, EventClass, IOFXMLError};
+use crate::iof::{xml, ClassEntryFee, EntryFee, EventClass, IOFXMLError};
 use std::convert::TryFrom;
-use xmltree::Element;
 
-impl TryFrom<&Element> for EventClass {
+impl TryFrom<&xml::EventClass> for EventClass {
     type Error = IOFXMLError;
 
-    fn try_from(element: &Element) -> Result<Self, Self::Error> {
-        let id: u64 = numeric_contents(element, "EventClassId")
-            .ok_or("Event class id missing or malformed!")?;
-        let name = textual_contents(element, "ClassShortName")
+    fn try_from(event_class: &xml::EventClass) -> Result<Self, Self::Error> {
+        let name = event_class
+            .class_short_name
+            .clone()
             .ok_or("Missing short class name")?;
 
-        let mut fees: Vec<ClassEntryFee> = subelements(element, "ClassEntryFee")?;
-        fees.sort_by(|a, b| a.sequence.cmp(&b.sequence));
+        let mut fees: Vec<ClassEntryFee> = event_class
+            .class_entry_fees
+            .iter()
+            .map(ClassEntryFee::from)
+            .collect();
+        fees.sort_by_key(|f| f.sequence);
 
         Ok(EventClass {
-            id,
+            id: event_class.event_class_id,
             name,
             fee_ids: fees.into_iter().map(|f| f.id).collect(),
         })
@@ -23,27 +26,12 @@ impl TryFrom<&Element> for EventClass {
 }
 
 impl EventClass {
-    pub fn paid_direct_entry_fees(
-        &self,
-        birth_year: &u64,
-        entry_fees: &Vec<EntryFee>,
-    ) -> (f64, f64) {
-        let applicable_fee_ids = self
-            .fee_ids
+    pub fn direct_entry_fee_ids(&self, birth_year: &u64, entry_fees: &[EntryFee]) -> Vec<u64> {
+        self.fee_ids
             .iter()
-            .map(|fee_id| {
-                entry_fees
-                    .iter()
-                    .find(|fee| fee.id == *fee_id)
-                    .expect("Invalid entry fee!")
-            })
-            .filter(|fee| match (fee.from_year_of_birth, fee.to_year_of_birth) {
-                (Some(from_year), _) if birth_year < &from_year => false,
-                (_, Some(to_year)) if birth_year > &to_year => false,
-                _ => true,
-            })
+            .filter_map(|fee_id| EntryFee::find(fee_id, entry_fees))
+            .filter(|fee| fee.applies_to_birth_year(birth_year))
             .map(|fee| fee.id)
-            .collect();
-        EntryFee::paid_fees_from_fee_ids(&applicable_fee_ids, entry_fees)
+            .collect()
     }
 }
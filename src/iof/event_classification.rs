@@ -0,0 +1,47 @@
+use crate::iof::EventClassification;
+use std::fmt;
+use std::str::FromStr;
+
+impl EventClassification {
+    pub fn from_id(id: u64) -> Option<Self> {
+        match id {
+            1 => Some(EventClassification::Championship),
+            2 => Some(EventClassification::National),
+            3 => Some(EventClassification::Regional),
+            4 => Some(EventClassification::Local),
+            5 => Some(EventClassification::Club),
+            6 => Some(EventClassification::International),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for EventClassification {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "championship" => Ok(EventClassification::Championship),
+            "national" => Ok(EventClassification::National),
+            "regional" => Ok(EventClassification::Regional),
+            "local" => Ok(EventClassification::Local),
+            "club" => Ok(EventClassification::Club),
+            "international" => Ok(EventClassification::International),
+            _ => Err("Unrecognized event classification, should be one of championship, national, regional, local, club or international."),
+        }
+    }
+}
+
+impl fmt::Display for EventClassification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            EventClassification::Championship => "championship",
+            EventClassification::National => "national",
+            EventClassification::Regional => "regional",
+            EventClassification::Local => "local",
+            EventClassification::Club => "club",
+            EventClassification::International => "international",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -0,0 +1,64 @@
+use crate::iof::EventStatus;
+use std::fmt;
+use std::str::FromStr;
+
+impl EventStatus {
+    pub fn from_id(id: u64) -> Option<Self> {
+        match id {
+            1 => Some(EventStatus::Applied),
+            2 => Some(EventStatus::ApprovedByRegion),
+            3 => Some(EventStatus::Approved),
+            4 => Some(EventStatus::Created),
+            5 => Some(EventStatus::EntryOpened),
+            6 => Some(EventStatus::EntryPaused),
+            7 => Some(EventStatus::EntryClosed),
+            8 => Some(EventStatus::Live),
+            9 => Some(EventStatus::Completed),
+            10 => Some(EventStatus::Cancelled),
+            11 => Some(EventStatus::Reported),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for EventStatus {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "applied" => Ok(EventStatus::Applied),
+            "approved_by_region" => Ok(EventStatus::ApprovedByRegion),
+            "approved" => Ok(EventStatus::Approved),
+            "created" => Ok(EventStatus::Created),
+            "entry_opened" => Ok(EventStatus::EntryOpened),
+            "entry_paused" => Ok(EventStatus::EntryPaused),
+            "entry_closed" => Ok(EventStatus::EntryClosed),
+            "live" => Ok(EventStatus::Live),
+            "completed" => Ok(EventStatus::Completed),
+            "cancelled" => Ok(EventStatus::Cancelled),
+            "reported" => Ok(EventStatus::Reported),
+            "rescheduled" => Ok(EventStatus::Rescheduled),
+            _ => Err("Unrecognized event status."),
+        }
+    }
+}
+
+impl fmt::Display for EventStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            EventStatus::Applied => "applied",
+            EventStatus::ApprovedByRegion => "approved_by_region",
+            EventStatus::Approved => "approved",
+            EventStatus::Created => "created",
+            EventStatus::EntryOpened => "entry_opened",
+            EventStatus::EntryPaused => "entry_paused",
+            EventStatus::EntryClosed => "entry_closed",
+            EventStatus::Live => "live",
+            EventStatus::Completed => "completed",
+            EventStatus::Cancelled => "cancelled",
+            EventStatus::Reported => "reported",
+            EventStatus::Rescheduled => "rescheduled",
+        };
+        write!(f, "{}", name)
+    }
+}
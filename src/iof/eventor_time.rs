@@ -1,16 +1,37 @@
-use crate::iof::{textual_contents, EventorTime, IOFXMLError};
+use crate::iof::{xml, EventorTime, IOFXMLError};
+use chrono::{NaiveDate, NaiveTime, TimeZone};
+use chrono_tz::Tz;
 use std::convert::TryFrom;
-use xmltree::Element;
+use std::time::SystemTime;
 
-impl TryFrom<&Element> for EventorTime {
+// Eventor gives local Swedish time.
+pub const EVENTOR_TIME_ZONE: Tz = chrono_tz::Europe::Stockholm;
+
+impl TryFrom<&xml::Time> for EventorTime {
     type Error = IOFXMLError;
 
-    fn try_from(element: &Element) -> Result<Self, Self::Error> {
-        let date = textual_contents(element, "Date")
-            .ok_or("Eventor timestamp object is missing date!")?
-            .replace("-", "")
-            .parse::<u64>()
+    fn try_from(time: &xml::Time) -> Result<Self, Self::Error> {
+        let date = NaiveDate::parse_from_str(&time.date, "%Y-%m-%d")
             .map_err(|_| "Bad date in eventor timestamp object")?;
-        Ok(EventorTime { date })
+        let clock = match &time.clock {
+            Some(clock) => Some(
+                NaiveTime::parse_from_str(clock, "%H:%M:%S")
+                    .map_err(|_| "Bad clock in eventor timestamp object")?,
+            ),
+            None => None,
+        };
+        Ok(EventorTime { date, clock })
+    }
+}
+
+impl EventorTime {
+    // A time without a clock is taken as the start of the day. Times that are skipped when
+    // summer time begins are taken as UTC, which puts them an hour or two later than they are.
+    pub fn to_system_time(self) -> SystemTime {
+        let local = self.date.and_time(self.clock.unwrap_or(NaiveTime::MIN));
+        match EVENTOR_TIME_ZONE.from_local_datetime(&local).earliest() {
+            Some(time) => time.into(),
+            None => local.and_utc().into(),
+        }
     }
 }
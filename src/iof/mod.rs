@@ -1,26 +1,59 @@
-use std::convert::TryFrom;
+use chrono::{Datelike, NaiveDate, NaiveTime};
+use serde::Serialize;
 
 pub type IOFXMLError = &'static str;
 
 mod class_entry_fee;
 mod class_result;
 mod competitor;
+mod competitor_status;
+mod discipline;
 mod entrant;
 mod entry;
+mod entry_break;
 mod entry_fee;
 mod event;
 mod event_class;
+mod event_classification;
+mod event_status;
 mod eventor_time;
+pub use eventor_time::EVENTOR_TIME_ZONE;
 mod person_result;
 mod race;
+mod service_request;
+mod stream;
+mod team_result;
+mod v3;
+pub mod xml;
+
+mod document;
+pub use stream::{club_class_results, club_entries, is_club, is_club_entry, read_element};
+pub use document::{entry_fees, Document, event, event_classes, events, persons, service_requests};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum IofVersion {
+    V2_0_3,
+    V3_0,
+}
 
 #[derive(Debug)]
-struct Race {
-    id: u64,
-    date: EventorTime,
+pub struct Race {
+    pub id: u64,
+    pub name: Option<String>,
+    // E.g. Sprint, Middle or Long.
+    pub distance: Option<String>,
+    pub date: EventorTime,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+// A period when entries are open at a given fee level. The end of the last one is the deadline
+// for late entries.
+#[derive(Debug)]
+pub struct EntryBreak {
+    pub valid_from: Option<EventorTime>,
+    pub valid_to: Option<EventorTime>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
 pub struct Competitor {
     pub id: Option<u64>,
     pub given: String,
@@ -41,73 +74,188 @@ pub struct EventClass {
     pub fee_ids: Vec<u64>,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum CompetitorStatus {
+    Inactive,
+    Active,
+    Finished,
+    OK,
+    DidNotStart,
+    DidNotFinish,
+    MisPunch,
+    Disqualified,
+    NotCompeting,
+    SportingWithdrawal,
+    OverTime,
+    Moved,
+    MovedUp,
+    Cancelled,
+    Vacant,
+    Unknown,
+}
+
+#[derive(Debug)]
+pub struct RaceStatus {
+    pub event_race_id: Option<u64>,
+    pub status: CompetitorStatus,
+}
+
 #[derive(Debug)]
 pub struct PersonResult {
     pub competitor: Competitor,
-    pub dns: bool,
+    pub race_statuses: Vec<RaceStatus>,
+    // Only set for relay legs.
+    pub leg: Option<u64>,
+    pub organisation_id: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct TeamResult {
+    pub name: String,
+    pub person_results: Vec<PersonResult>,
 }
 
 #[derive(Debug)]
 pub struct ClassResult {
     pub event_class_id: u64,
+    pub class_name: Option<String>,
     pub event_race_id: u64,
     pub person_results: Vec<PersonResult>,
+    pub team_results: Vec<TeamResult>,
 }
 
 #[derive(Debug)]
 pub struct Event {
     pub id: u64,
     pub name: String,
-    races: Vec<Race>,
+    pub classification: Option<EventClassification>,
+    pub discipline: Option<Discipline>,
+    pub status: Option<EventStatus>,
+    pub organiser_ids: Vec<u64>,
+    pub entry_breaks: Vec<EntryBreak>,
+    pub web_url: Option<String>,
+    // When the event was last changed in Eventor, used to tell if cached responses are stale.
+    pub modify_date: Option<EventorTime>,
+    pub races: Vec<Race>,
+}
+
+// The level of an event, as given by EventClassificationId in Eventor.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EventClassification {
+    Championship,
+    National,
+    Regional,
+    Local,
+    Club,
+    International,
+}
+
+// The sport of an event, as given by DisciplineId in Eventor.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Discipline {
+    Foot,
+    MountainBike,
+    Ski,
+    Trail,
+}
+
+// How far an event has come, as given by EventStatusId in Eventor.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EventStatus {
+    Applied,
+    ApprovedByRegion,
+    Approved,
+    Created,
+    EntryOpened,
+    EntryPaused,
+    EntryClosed,
+    Live,
+    Completed,
+    Cancelled,
+    Reported,
+    // Only in IOF XML 3.0, e.g. when an event is postponed.
+    Rescheduled,
+}
+
+#[derive(Debug)]
+pub struct TeamMember {
+    pub person_id: Option<u64>,
+    pub leg: u64,
 }
 
 #[derive(Debug)]
 pub enum Entrant {
     Unknown,
     Individual(u64),
-    Team,
+    Team { name: String, members: Vec<TeamMember> },
 }
 
 #[derive(Debug)]
 pub struct Entry {
+    pub id: Option<u64>,
     pub entrant: Entrant,
-    fee_ids: Vec<u64>,
+    pub fee_ids: Vec<u64>,
+    pub event_class_id: Option<u64>,
+    // The competitor's own punching card, if one was given when entering.
+    pub card: Option<CCard>,
 }
 
+#[derive(Debug, Clone)]
+pub struct CCard {
+    pub id: String,
+}
+
+// Something booked at the event besides the entry itself, such as accommodation or meals.
+#[derive(Debug)]
+pub struct ServiceRequest {
+    pub person_id: Option<u64>,
+    // When the request gives the person's name.
+    pub person: Option<Competitor>,
+    pub name: String,
+    pub quantity: f64,
+    // Total for the requested quantity.
+    pub amount: f64,
+}
+
+// A local time in Sweden, which is what Eventor gives.
 #[derive(Debug, Clone, Copy)]
 pub struct EventorTime {
-    pub date: u64,
+    pub date: NaiveDate,
+    pub clock: Option<NaiveTime>,
 }
 
-#[derive(Debug)]
-enum ValueOperator {
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ValueOperator {
     Fixed,
     Percent,
 }
 
 #[derive(Debug)]
 pub struct EntryFee {
-    id: u64,
+    pub id: u64,
     pub name: String,
-    amount: f64,
-    operator: ValueOperator,
-    from_year_of_birth: Option<u64>,
-    to_year_of_birth: Option<u64>,
+    pub amount: f64,
+    pub operator: ValueOperator,
+    pub from_year_of_birth: Option<u64>,
+    pub to_year_of_birth: Option<u64>,
+    // Set when the fee only applies to a single stage of a multi-day event.
+    pub event_race_id: Option<u64>,
+    // Guessed from the name, unless the club lists its names for card rental fees.
+    pub card_rental: bool,
 }
 
 fn textual_contents(element: &xmltree::Element, child_name: &str) -> Option<String> {
     element
         .get_child(child_name)
-        .map(|element| element.get_text())
-        .flatten()
+        .and_then(|element| element.get_text())
         .map(|c| c.to_string())
 }
 
-pub fn year_from_date_string(date_string: &String) -> Option<u64> {
-    if date_string.len() < 4 {
-        None
-    } else {
-        date_string[0..4].parse::<u64>().ok()
+// The year of a date given as YYYY-MM-DD, such as a birth date. Some lists only give the year.
+pub fn year_of_date(date_string: &str) -> Option<u64> {
+    match NaiveDate::parse_from_str(date_string, "%Y-%m-%d") {
+        Ok(date) => u64::try_from(date.year()).ok(),
+        Err(_) => date_string.parse::<u64>().ok().filter(|year| (1000..=9999).contains(year)),
     }
 }
 
@@ -117,21 +265,3 @@ fn numeric_contents<T: std::str::FromStr>(
 ) -> Option<T> {
     textual_contents(element, child_name)?.parse::<T>().ok()
 }
-
-pub fn subelements<'a, T: TryFrom<&'a xmltree::Element, Error = IOFXMLError>>(
-    element: &'a xmltree::Element,
-    child_name: &str,
-) -> Result<Vec<T>, IOFXMLError> {
-    element
-        .children
-        .iter()
-        .filter_map(|node| -> Option<Result<T, IOFXMLError>> {
-            match node {
-                xmltree::XMLNode::Element(element) if element.name == child_name => {
-                    Some(element.try_into())
-                }
-                _ => None,
-            }
-        })
-        .collect::<Result<Vec<T>, IOFXMLError>>()
-}
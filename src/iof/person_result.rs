@@ -1,30 +1,77 @@
-use crate::iof::{Competitor, IOFXMLError, PersonResult};
+use crate::iof::{xml, Competitor, CompetitorStatus, IOFXMLError, PersonResult, RaceStatus};
 use std::convert::TryFrom;
-use xmltree::Element;
 
-impl TryFrom<&Element> for PersonResult {
+fn status(result: Option<&xml::Result>) -> Result<CompetitorStatus, IOFXMLError> {
+    let status = result
+        .ok_or("Missing result element from person result")?
+        .competitor_status
+        .as_ref()
+        .ok_or("Missing competitor status from result")?;
+    Ok(status
+        .value
+        .parse::<CompetitorStatus>()
+        .unwrap_or(CompetitorStatus::Unknown))
+}
+
+impl TryFrom<&xml::RaceResult> for RaceStatus {
     type Error = IOFXMLError;
 
-    fn try_from(element: &Element) -> Result<Self, Self::Error> {
-        let competitor: Competitor = element
-            .get_child("Person")
-            .ok_or("No person was specified for person result.")?
-            .try_into()?;
-
-        // If this is a multi-day event, the Result element is buried within
-        // a RaceResult element.
-        let parent = element.get_child("RaceResult").unwrap_or(element);
-        let status = parent
-            .get_child("Result")
-            .ok_or("Missing result element from person result")?
-            .get_child("CompetitorStatus")
-            .ok_or("Missing competitor status from result")?;
-
-        let dns = match status.attributes.get("value") {
-            Some(v) => v == "DidNotStart",
-            _ => false,
-        };
-
-        Ok(PersonResult { competitor, dns })
+    fn try_from(race_result: &xml::RaceResult) -> Result<Self, Self::Error> {
+        Ok(RaceStatus {
+            event_race_id: race_result.event_race_id,
+            status: status(race_result.result.as_ref())?,
+        })
+    }
+}
+
+impl PersonResult {
+    // The status of the first race, which is the only one for a relay leg.
+    pub fn status(&self) -> CompetitorStatus {
+        self.race_statuses
+            .first()
+            .map_or(CompetitorStatus::Unknown, |race_status| race_status.status)
+    }
+
+    // Lists asked for by organisation often leave the organisation out, and the runner is then
+    // taken to be from the organisation.
+    pub fn is_from(&self, organisation_id: u64) -> bool {
+        self.organisation_id.is_none_or(|id| id == organisation_id)
+    }
+}
+
+impl TryFrom<&xml::PersonResult> for PersonResult {
+    type Error = IOFXMLError;
+
+    fn try_from(person_result: &xml::PersonResult) -> Result<Self, Self::Error> {
+        let competitor: Competitor = (&person_result.person).try_into()?;
+
+        // If this is a multi-day event, there is one RaceResult element per stage, each
+        // with its own Result element. Otherwise the Result element is directly below.
+        let mut race_statuses = person_result
+            .race_results
+            .iter()
+            .map(|race_result| race_result.try_into())
+            .collect::<Result<Vec<RaceStatus>, _>>()?;
+        if race_statuses.is_empty() {
+            race_statuses.push(RaceStatus {
+                event_race_id: None,
+                status: status(person_result.result.as_ref())?,
+            });
+        }
+
+        // Relay legs are numbered by the team sequence, which may be given on the result.
+        let leg = person_result.team_sequence.or_else(|| {
+            person_result
+                .result
+                .as_ref()
+                .and_then(|result| result.team_sequence)
+        });
+
+        Ok(PersonResult {
+            competitor,
+            race_statuses,
+            leg,
+            organisation_id: person_result.organisation.as_ref().and_then(|organisation| organisation.organisation_id),
+        })
     }
 }
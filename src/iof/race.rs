@@ -1,19 +1,17 @@
-use crate::iof::Race;
-use crate::iof::{numeric_contents, EventorTime, IOFXMLError};
+use crate::iof::{xml, EventorTime, IOFXMLError, Race};
 use std::convert::TryFrom;
-use xmltree::Element;
 
-impl TryFrom<&Element> for Race {
+impl TryFrom<&xml::EventRace> for Race {
     type Error = IOFXMLError;
 
-    fn try_from(element: &Element) -> Result<Self, Self::Error> {
-        let id: u64 = numeric_contents(element, "EventRaceId")
-            .ok_or("Event race id missing or malformed!")?;
-        let date: EventorTime = element
-            .get_child("RaceDate")
-            .ok_or("Event race missing race date!")?
-            .try_into()?;
+    fn try_from(race: &xml::EventRace) -> Result<Self, Self::Error> {
+        let date: EventorTime = (&race.race_date).try_into()?;
 
-        Ok(Race { id, date })
+        Ok(Race {
+            id: race.event_race_id,
+            name: race.name.clone(),
+            distance: race.race_distance.clone(),
+            date,
+        })
     }
 }
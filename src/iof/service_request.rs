@@ -0,0 +1,46 @@
+use crate::iof::{xml, Competitor, IOFXMLError, ServiceRequest};
+use std::convert::TryFrom;
+
+impl TryFrom<&xml::PersonServiceRequest> for ServiceRequest {
+    type Error = IOFXMLError;
+
+    fn try_from(request: &xml::PersonServiceRequest) -> Result<Self, Self::Error> {
+        let person_id = request
+            .person
+            .as_ref()
+            .and_then(|person| person.person_id)
+            .or(request.person_id);
+        let (service, requested_quantity, quantity, amount) = match &request.service_request {
+            Some(r) => (&r.service, r.requested_quantity, r.quantity, &r.amount),
+            None => (&request.service, request.requested_quantity, request.quantity, &request.amount),
+        };
+        let service = service.as_ref().ok_or("Service request is missing the service!")?;
+        let quantity = requested_quantity.or(quantity).unwrap_or(1f64);
+        // The total is either given for the request, or as a price per unit for the service.
+        let amount = match amount {
+            Some(amount) => amount.amount,
+            None => {
+                service
+                    .unit_price
+                    .as_ref()
+                    .or(service.amount.as_ref())
+                    .ok_or("Service request amount missing or malformed!")?
+                    .amount
+                    * quantity
+            }
+        };
+
+        let person = request
+            .person
+            .as_ref()
+            .and_then(|person| Competitor::try_from(person).ok());
+
+        Ok(ServiceRequest {
+            person_id,
+            person,
+            name: service.name.clone(),
+            quantity,
+            amount,
+        })
+    }
+}
@@ -0,0 +1,301 @@
+use crate::iof::document::Document;
+use crate::iof::v3::{self, V3};
+use crate::iof::{numeric_contents, xml, ClassResult, Entry, IOFXMLError, IofVersion, PersonResult, TeamResult};
+use quick_xml::events::{BytesStart, Event as XmlEvent};
+use quick_xml::{Reader, Writer};
+use serde::de::DeserializeOwned;
+use std::io::BufRead;
+use xmltree::Element;
+
+// Streaming extraction from result and entry lists, in either IOF XML version. The document is
+// read element by element from its file, and only the club's own results and entries are
+// deserialized, or in IOF XML 3.0 made into element trees, so a large event such as O-Ringen
+// costs about as much as a small one. Neither the text nor a tree of the whole document is built.
+
+const MALFORMED: IOFXMLError = "The XML document is malformed.";
+
+fn deserialize<T: DeserializeOwned>(element: &[u8]) -> Result<T, IOFXMLError> {
+    quick_xml::de::from_reader(element).map_err(|_| "The XML document does not follow IOF XML 2.0.3.")
+}
+
+fn tree(element: &[u8]) -> Result<Element, IOFXMLError> {
+    Element::parse(element).map_err(|_| MALFORMED)
+}
+
+// Lists asked for by organisation often leave the organisation out, and everyone is then kept.
+pub fn is_club(organisation: Option<&xml::Organisation>, organisation_id: u64) -> bool {
+    organisation
+        .and_then(|organisation| organisation.organisation_id)
+        .is_none_or(|id| id == organisation_id)
+}
+
+// Whether any competitor of an entry is from the organisation, or no organisation is given.
+pub fn is_club_entry(entry: &xml::Entry, organisation_id: u64) -> bool {
+    let organisations: Vec<&xml::Organisation> = entry
+        .competitor
+        .iter()
+        .filter_map(|competitor| competitor.organisation.as_ref())
+        .chain(entry.team_competitors.iter().filter_map(|competitor| competitor.organisation.as_ref()))
+        .collect();
+    organisations.is_empty() || organisations.iter().any(|organisation| is_club(Some(organisation), organisation_id))
+}
+
+// Reads the element just started to its end, and returns it as a document of its own.
+pub fn read_element<R: BufRead>(reader: &mut Reader<R>, start: BytesStart) -> Result<Vec<u8>, IOFXMLError> {
+    let mut writer = Writer::new(Vec::new());
+    writer.write_event(XmlEvent::Start(start)).map_err(|_| MALFORMED)?;
+    let mut buffer = vec![];
+    let mut depth = 0;
+    loop {
+        buffer.clear();
+        let event = reader.read_event_into(&mut buffer).map_err(|_| MALFORMED)?;
+        match &event {
+            XmlEvent::Start(_) => depth += 1,
+            XmlEvent::End(_) if depth == 0 => {
+                writer.write_event(event).map_err(|_| MALFORMED)?;
+                return Ok(writer.into_inner());
+            }
+            XmlEvent::End(_) => depth -= 1,
+            XmlEvent::Eof => return Err(MALFORMED),
+            _ => {}
+        }
+        writer.write_event(event).map_err(|_| MALFORMED)?;
+    }
+}
+
+// The elements of a list that are read whole, and the ends of the class results they are in.
+enum Item {
+    Element(String, Vec<u8>),
+    End(String),
+}
+
+// Reads the document, handing each element named in `names` whole to `handle`, as well as the
+// end of every other element. The event is skipped, as it has classes of its own.
+fn stream(document: &Document, names: &[&str], mut handle: impl FnMut(Item) -> Result<(), IOFXMLError>) -> Result<(), IOFXMLError> {
+    let mut reader = document.reader()?;
+    let mut buffer = vec![];
+    loop {
+        buffer.clear();
+        match reader.read_event_into(&mut buffer).map_err(|_| MALFORMED)? {
+            XmlEvent::Start(start) if start.name().as_ref() == b"Event" => {
+                let end = start.to_end().into_owned();
+                reader.read_to_end_into(end.name(), &mut vec![]).map_err(|_| MALFORMED)?;
+            }
+            XmlEvent::Start(start) => {
+                let name = String::from_utf8_lossy(start.name().as_ref()).to_string();
+                if names.contains(&name.as_str()) {
+                    let element = read_element(&mut reader, start.into_owned())?;
+                    handle(Item::Element(name, element))?;
+                }
+            }
+            XmlEvent::End(end) => handle(Item::End(String::from_utf8_lossy(end.name().as_ref()).to_string()))?,
+            XmlEvent::Eof => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+// Class results with only the persons and teams of the given organisation. A team is kept whole
+// if any of its runners is from the organisation, since the team fee is shared by all of them.
+pub fn club_class_results(document: &Document, organisation_id: u64) -> Result<Vec<ClassResult>, IOFXMLError> {
+    match document.version() {
+        IofVersion::V2_0_3 => club_class_results_v2(document, organisation_id),
+        IofVersion::V3_0 => club_class_results_v3(document, organisation_id),
+    }
+}
+
+fn club_class_results_v2(document: &Document, organisation_id: u64) -> Result<Vec<ClassResult>, IOFXMLError> {
+    let mut class_results: Vec<xml::ClassResult> = vec![];
+    let mut event_class: Option<xml::ClassReference> = None;
+    let mut person_results: Vec<xml::PersonResult> = vec![];
+    let mut team_results: Vec<xml::TeamResult> = vec![];
+    stream(document, &["EventClass", "PersonResult", "TeamResult"], |item| {
+        match item {
+            Item::Element(name, element) => match name.as_str() {
+                "EventClass" => event_class = Some(deserialize(&element)?),
+                "PersonResult" => {
+                    let person_result: xml::PersonResult = deserialize(&element)?;
+                    if is_club(person_result.organisation.as_ref(), organisation_id) {
+                        person_results.push(person_result);
+                    }
+                }
+                _ => {
+                    let team_result: xml::TeamResult = deserialize(&element)?;
+                    if team_result
+                        .person_results
+                        .iter()
+                        .any(|runner| is_club(runner.organisation.as_ref(), organisation_id))
+                    {
+                        team_results.push(team_result);
+                    }
+                }
+            },
+            Item::End(name) if name == "ClassResult" => {
+                let event_class = event_class
+                    .take()
+                    .ok_or("No event class was specified for class result.")?;
+                if !person_results.is_empty() || !team_results.is_empty() {
+                    class_results.push(xml::ClassResult {
+                        event_class,
+                        person_results: std::mem::take(&mut person_results),
+                        team_results: std::mem::take(&mut team_results),
+                    });
+                }
+            }
+            Item::End(_) => {}
+        }
+        Ok(())
+    })?;
+
+    class_results.iter().map(|class_result| class_result.try_into()).collect()
+}
+
+fn club_class_results_v3(document: &Document, organisation_id: u64) -> Result<Vec<ClassResult>, IOFXMLError> {
+    let mut class_results: Vec<ClassResult> = vec![];
+    let mut class: Option<Element> = None;
+    let mut person_results: Vec<PersonResult> = vec![];
+    let mut team_results: Vec<TeamResult> = vec![];
+    stream(document, &["Class", "PersonResult", "TeamResult"], |item| {
+        match item {
+            Item::Element(name, element) => {
+                let element = tree(&element)?;
+                match name.as_str() {
+                    "Class" => class = Some(element),
+                    "PersonResult" => {
+                        let person_result: PersonResult = V3(&element).try_into()?;
+                        if person_result.is_from(organisation_id) {
+                            person_results.push(person_result);
+                        }
+                    }
+                    _ => {
+                        let team_result: TeamResult = V3(&element).try_into()?;
+                        if team_result.person_results.iter().any(|runner| runner.is_from(organisation_id)) {
+                            team_results.push(team_result);
+                        }
+                    }
+                }
+            }
+            Item::End(name) if name == "ClassResult" => {
+                let class = class.take().ok_or("Missing class in class result.")?;
+                if !person_results.is_empty() || !team_results.is_empty() {
+                    class_results.push(v3::class_result(
+                        &class,
+                        std::mem::take(&mut person_results),
+                        std::mem::take(&mut team_results),
+                    )?);
+                }
+            }
+            Item::End(_) => {}
+        }
+        Ok(())
+    })?;
+
+    Ok(class_results)
+}
+
+// Entries with a competitor from the given organisation, or with no organisation given.
+pub fn club_entries(document: &Document, organisation_id: u64) -> Result<Vec<Entry>, IOFXMLError> {
+    let mut club_entries: Vec<Entry> = vec![];
+    match document.version() {
+        IofVersion::V2_0_3 => stream(document, &["Entry"], |item| {
+            if let Item::Element(_, element) = item {
+                let entry: xml::Entry = deserialize(&element)?;
+                if is_club_entry(&entry, organisation_id) {
+                    club_entries.push((&entry).try_into()?);
+                }
+            }
+            Ok(())
+        })?,
+        IofVersion::V3_0 => stream(document, &["PersonEntry", "TeamEntry"], |item| {
+            if let Item::Element(_, element) = item {
+                let element = tree(&element)?;
+                // The organisation of the entry, and of each team member.
+                let organisations: Vec<Option<u64>> = [&element]
+                    .into_iter()
+                    .chain(element.children.iter().filter_map(|node| node.as_element()).filter(|e| e.name == "TeamEntryPerson"))
+                    .filter_map(|e| e.get_child("Organisation"))
+                    .map(|organisation| numeric_contents(organisation, "Id"))
+                    .collect();
+                if organisations.is_empty() || organisations.iter().any(|id| id.is_none_or(|id| id == organisation_id)) {
+                    club_entries.push(V3(&element).try_into()?);
+                }
+            }
+            Ok(())
+        })?,
+    }
+
+    Ok(club_entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(text: &str) -> Document {
+        Document::parse(text.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_club_class_results_v2() {
+        let document = document(
+            "<ResultList><Event><EventId>1</EventId></Event><ClassResult><EventClass><EventClassId>10</EventClassId>\
+             <ClassRaceInfo><EventRaceId>100</EventRaceId></ClassRaceInfo></EventClass>\
+             <PersonResult><Person><PersonId>1</PersonId><PersonName><Family>A</Family><Given>B</Given></PersonName></Person><Organisation><OrganisationId>224</OrganisationId></Organisation>\
+             <Result><CompetitorStatus value=\"OK\"/></Result></PersonResult>\
+             <PersonResult><Person><PersonId>2</PersonId><PersonName><Family>C</Family><Given>D</Given></PersonName></Person><Organisation><OrganisationId>5</OrganisationId></Organisation>\
+             <Result><CompetitorStatus value=\"OK\"/></Result></PersonResult></ClassResult>\
+             <ClassResult><EventClass><EventClassId>11</EventClassId></EventClass>\
+             <PersonResult><Person><PersonId>3</PersonId><PersonName><Family>E</Family><Given>F</Given></PersonName></Person><Organisation><OrganisationId>5</OrganisationId></Organisation>\
+             <Result><CompetitorStatus value=\"OK\"/></Result></PersonResult></ClassResult></ResultList>",
+        );
+        let class_results = club_class_results(&document, 224).unwrap();
+        assert_eq!(class_results.len(), 1);
+        assert_eq!(class_results[0].event_class_id, 10);
+        assert_eq!(class_results[0].person_results.len(), 1);
+        assert_eq!(class_results[0].person_results[0].competitor.id, Some(1));
+    }
+
+    #[test]
+    fn test_club_class_results_v3() {
+        // The classes of the event are not those of the class results.
+        let document = document(
+            "<ResultList xmlns=\"http://www.orienteering.org/datastandard/3.0\" iofVersion=\"3.0\">\
+             <Event><Id>1</Id><Class><Id>99</Id></Class></Event>\
+             <ClassResult><Class><Id>10</Id><Name>H21</Name></Class>\
+             <PersonResult><Person><Id>1</Id><Name><Family>A</Family><Given>B</Given></Name></Person>\
+             <Organisation><Id>224</Id></Organisation><Result raceNumber=\"2\"><Status>OK</Status></Result></PersonResult>\
+             <PersonResult><Person><Id>2</Id><Name><Family>C</Family><Given>D</Given></Name></Person>\
+             <Organisation><Id>5</Id></Organisation><Result><Status>OK</Status></Result></PersonResult>\
+             <TeamResult><Name>Lag</Name><TeamMemberResult><Person><Id>3</Id><Name><Family>E</Family><Given>F</Given></Name></Person><Organisation><Id>5</Id></Organisation>\
+             <Result><Leg>1</Leg><Status>OK</Status></Result></TeamMemberResult></TeamResult></ClassResult></ResultList>",
+        );
+        let class_results = club_class_results(&document, 224).unwrap();
+        assert_eq!(class_results.len(), 1);
+        assert_eq!(class_results[0].event_class_id, 10);
+        assert_eq!(class_results[0].class_name.as_deref(), Some("H21"));
+        assert_eq!(class_results[0].event_race_id, 2);
+        assert_eq!(class_results[0].person_results.len(), 1);
+        assert!(class_results[0].team_results.is_empty());
+    }
+
+    #[test]
+    fn test_club_entries_v3() {
+        let document = document(
+            "<EntryList xmlns=\"http://www.orienteering.org/datastandard/3.0\" iofVersion=\"3.0\">\
+             <PersonEntry><Id>1</Id><Person><Id>1</Id></Person><Organisation><Id>224</Id></Organisation></PersonEntry>\
+             <PersonEntry><Id>2</Id><Person><Id>2</Id></Person><Organisation><Id>5</Id></Organisation></PersonEntry>\
+             <PersonEntry><Id>3</Id><Person><Id>3</Id></Person></PersonEntry>\
+             <TeamEntry><Id>4</Id><Name>Lag</Name><Organisation><Id>5</Id></Organisation>\
+             <TeamEntryPerson><Person><Id>4</Id></Person><Organisation><Id>224</Id></Organisation></TeamEntryPerson></TeamEntry>\
+             </EntryList>",
+        );
+        let ids: Vec<Option<u64>> = club_entries(&document, 224).unwrap().iter().map(|entry| entry.id).collect();
+        assert_eq!(ids, vec![Some(1), Some(3), Some(4)]);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let document = document("<ResultList><ClassResult><EventClass><EventClassId>10</EventClassId></ClassResult></ResultList>");
+        assert!(club_class_results(&document, 224).is_err());
+    }
+}
@@ -0,0 +1,27 @@
+use crate::iof::{xml, IOFXMLError, PersonResult, TeamResult};
+use std::convert::TryFrom;
+
+impl TryFrom<&xml::TeamResult> for TeamResult {
+    type Error = IOFXMLError;
+
+    fn try_from(team_result: &xml::TeamResult) -> Result<Self, Self::Error> {
+        let name = team_result.team_name.clone().unwrap_or_default();
+        let mut person_results = team_result
+            .person_results
+            .iter()
+            .map(|person_result| person_result.try_into())
+            .collect::<Result<Vec<PersonResult>, _>>()?;
+
+        // Not all result lists state the leg explicitly. The runners are then listed in leg order.
+        for (index, person_result) in person_results.iter_mut().enumerate() {
+            if person_result.leg.is_none() {
+                person_result.leg = Some(index as u64 + 1);
+            }
+        }
+
+        Ok(TeamResult {
+            name,
+            person_results,
+        })
+    }
+}
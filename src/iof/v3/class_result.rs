@@ -0,0 +1,106 @@
+use super::{subelements, V3};
+use crate::iof::{numeric_contents, textual_contents, IOFXMLError};
+use crate::iof::{ClassResult, CompetitorStatus, PersonResult, RaceStatus, TeamResult};
+use std::convert::TryFrom;
+use xmltree::Element;
+
+impl TryFrom<V3<'_>> for RaceStatus {
+    type Error = IOFXMLError;
+
+    fn try_from(V3(element): V3) -> Result<Self, Self::Error> {
+        let event_race_id = element
+            .attributes
+            .get("raceNumber")
+            .and_then(|race_number| race_number.parse::<u64>().ok());
+        let status = textual_contents(element, "Status").map_or(CompetitorStatus::Unknown, |v| {
+            v.parse::<CompetitorStatus>().unwrap_or(CompetitorStatus::Unknown)
+        });
+
+        Ok(RaceStatus {
+            event_race_id,
+            status,
+        })
+    }
+}
+
+// Both a PersonResult and a TeamMemberResult, where the leg is given on the result.
+impl TryFrom<V3<'_>> for PersonResult {
+    type Error = IOFXMLError;
+
+    fn try_from(V3(element): V3) -> Result<Self, Self::Error> {
+        let competitor = V3(element
+            .get_child("Person")
+            .ok_or("No person was specified for person result.")?)
+            .try_into()?;
+        // There is one Result element per race for multi-race events.
+        let race_statuses: Vec<RaceStatus> = subelements(element, "Result")?;
+        if race_statuses.is_empty() {
+            return Err("Missing result element from person result");
+        }
+        let leg = element
+            .get_child("Result")
+            .and_then(|result| numeric_contents(result, "Leg"));
+
+        Ok(PersonResult {
+            competitor,
+            race_statuses,
+            leg,
+            organisation_id: element
+                .get_child("Organisation")
+                .and_then(|organisation| numeric_contents(organisation, "Id")),
+        })
+    }
+}
+
+impl TryFrom<V3<'_>> for TeamResult {
+    type Error = IOFXMLError;
+
+    fn try_from(V3(element): V3) -> Result<Self, Self::Error> {
+        let name = textual_contents(element, "Name").unwrap_or_default();
+        let mut person_results: Vec<PersonResult> = subelements(element, "TeamMemberResult")?;
+
+        // As for IOF XML 2.0.3, runners without a leg are taken to be listed in leg order.
+        for (index, person_result) in person_results.iter_mut().enumerate() {
+            if person_result.leg.is_none() {
+                person_result.leg = Some(index as u64 + 1);
+            }
+        }
+
+        Ok(TeamResult {
+            name,
+            person_results,
+        })
+    }
+}
+
+impl TryFrom<V3<'_>> for ClassResult {
+    type Error = IOFXMLError;
+
+    fn try_from(V3(element): V3) -> Result<Self, Self::Error> {
+        let class = element.get_child("Class").ok_or("Missing class in class result.")?;
+        class_result(class, subelements(element, "PersonResult")?, subelements(element, "TeamResult")?)
+    }
+}
+
+// A class result from its class element and the results read from it, which may be only some of
+// them, as when streaming.
+pub fn class_result(class: &Element, person_results: Vec<PersonResult>, team_results: Vec<TeamResult>) -> Result<ClassResult, IOFXMLError> {
+    let event_class_id = numeric_contents(class, "Id").ok_or("Missing class id in class result.")?;
+    let class_name = textual_contents(class, "ShortName").or_else(|| textual_contents(class, "Name"));
+
+    // Results for a single race event need not give the race number, and then it is the first race.
+    let event_race_id = person_results
+        .iter()
+        .chain(team_results.iter().flat_map(|team| team.person_results.iter()))
+        .flat_map(|person_result| person_result.race_statuses.iter())
+        .find_map(|race_status| race_status.event_race_id)
+        .unwrap_or(1);
+
+    Ok(ClassResult {
+        event_class_id,
+        class_name,
+        event_race_id,
+        person_results,
+        team_results,
+    })
+}
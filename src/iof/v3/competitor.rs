@@ -0,0 +1,25 @@
+use super::V3;
+use crate::iof::{numeric_contents, textual_contents, year_of_date, Competitor, IOFXMLError};
+use std::convert::TryFrom;
+
+impl TryFrom<V3<'_>> for Competitor {
+    type Error = IOFXMLError;
+
+    fn try_from(V3(element): V3) -> Result<Self, Self::Error> {
+        let id = numeric_contents(element, "Id");
+        let name_element = element.get_child("Name").ok_or("No name given for person")?;
+        let family = textual_contents(name_element, "Family")
+            .ok_or("No family name specified for competitor.")?;
+        let given = textual_contents(name_element, "Given")
+            .ok_or("No given name specified for competitor.")?;
+        let birth_year = textual_contents(element, "BirthDate")
+            .and_then(|date| year_of_date(&date));
+
+        Ok(Competitor {
+            id,
+            given,
+            family,
+            birth_year,
+        })
+    }
+}
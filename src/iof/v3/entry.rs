@@ -0,0 +1,52 @@
+use super::{children, V3};
+use crate::iof::{numeric_contents, textual_contents, IOFXMLError};
+use crate::iof::{CCard, Entrant, Entry, TeamMember};
+use std::convert::TryFrom;
+
+// Both a PersonEntry and a TeamEntry.
+impl TryFrom<V3<'_>> for Entry {
+    type Error = IOFXMLError;
+
+    fn try_from(V3(element): V3) -> Result<Self, Self::Error> {
+        let id = numeric_contents(element, "Id");
+        let entrant = if element.name == "TeamEntry" {
+            let name = textual_contents(element, "Name").unwrap_or_default();
+            let mut members: Vec<TeamMember> = children(element, "TeamEntryPerson")
+                .enumerate()
+                .map(|(index, member)| TeamMember {
+                    person_id: member
+                        .get_child("Person")
+                        .and_then(|person| numeric_contents(person, "Id")),
+                    // Runners without a leg are taken to be listed in leg order.
+                    leg: numeric_contents(member, "Leg").unwrap_or(index as u64 + 1),
+                })
+                .collect();
+            members.sort_by_key(|m| m.leg);
+            Entrant::Team { name, members }
+        } else {
+            match element
+                .get_child("Person")
+                .and_then(|person| numeric_contents(person, "Id"))
+            {
+                Some(id) => Entrant::Individual(id),
+                None => Entrant::Unknown,
+            }
+        };
+        let fee_ids = children(element, "AssignedFee")
+            .filter_map(|assigned_fee| assigned_fee.get_child("Fee"))
+            .filter_map(|fee| numeric_contents(fee, "Id"))
+            .collect();
+        let event_class_id = element
+            .get_child("Class")
+            .and_then(|class| numeric_contents(class, "Id"));
+        let card = textual_contents(element, "ControlCard").map(|id| CCard { id });
+
+        Ok(Entry {
+            id,
+            entrant,
+            fee_ids,
+            event_class_id,
+            card,
+        })
+    }
+}
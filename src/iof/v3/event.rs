@@ -0,0 +1,72 @@
+use super::{children, date_and_time, date_time, subelements, V3};
+use crate::iof::{numeric_contents, textual_contents, IOFXMLError};
+use crate::iof::{Event, EventClassification, EventStatus, Race};
+use std::convert::TryFrom;
+
+impl TryFrom<V3<'_>> for Race {
+    type Error = IOFXMLError;
+
+    fn try_from(V3(element): V3) -> Result<Self, Self::Error> {
+        // There are no race ids in IOF XML 3.0. Results and fees refer to the race number instead.
+        let id: u64 = numeric_contents(element, "RaceNumber")
+            .ok_or("Race number missing or malformed!")?;
+        let name = textual_contents(element, "Name");
+        let distance = textual_contents(element, "Discipline");
+        let date = date_and_time(element.get_child("StartTime").ok_or("Race missing start time!")?)?;
+
+        Ok(Race { id, name, distance, date })
+    }
+}
+
+impl TryFrom<V3<'_>> for Event {
+    type Error = IOFXMLError;
+
+    fn try_from(V3(element): V3) -> Result<Self, Self::Error> {
+        let id: u64 = numeric_contents(element, "Id").ok_or("Event id missing or malformed!")?;
+        let name = textual_contents(element, "Name").ok_or("Event name missing!")?;
+        let mut races: Vec<Race> = subelements(element, "Race")?;
+        // A single race event may leave out the race and give the start time for the event.
+        if races.is_empty() {
+            let date = date_and_time(element.get_child("StartTime").ok_or("Event missing start time!")?)?;
+            races.push(Race { id: 1, name: None, distance: None, date });
+        }
+        let classification = textual_contents(element, "Classification").and_then(|c| match c.as_str() {
+            "International" => Some(EventClassification::International),
+            "National" => Some(EventClassification::National),
+            "Regional" => Some(EventClassification::Regional),
+            "Local" => Some(EventClassification::Local),
+            "Club" => Some(EventClassification::Club),
+            _ => None,
+        });
+        let status = textual_contents(element, "Status").and_then(|s| match s.as_str() {
+            "Planned" => Some(EventStatus::Created),
+            "Applied" | "Proposed" => Some(EventStatus::Applied),
+            "Sanctioned" => Some(EventStatus::Approved),
+            "Canceled" => Some(EventStatus::Cancelled),
+            "Rescheduled" => Some(EventStatus::Rescheduled),
+            _ => None,
+        });
+        let organiser_ids = children(element, "Organiser")
+            .filter_map(|organiser| numeric_contents(organiser, "Id"))
+            .collect();
+        let web_url = textual_contents(element, "URL");
+        let modify_date = match element.attributes.get("modifyTime") {
+            Some(modify_time) => Some(date_time(modify_time)?),
+            None => None,
+        };
+
+        Ok(Event {
+            id,
+            name,
+            classification,
+            // IOF XML 3.0 has no sport for the event.
+            discipline: None,
+            status,
+            organiser_ids,
+            entry_breaks: vec![],
+            web_url,
+            modify_date,
+            races,
+        })
+    }
+}
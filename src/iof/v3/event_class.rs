@@ -0,0 +1,24 @@
+use super::children;
+use super::V3;
+use crate::iof::{numeric_contents, textual_contents, EventClass, IOFXMLError};
+use std::convert::TryFrom;
+
+impl TryFrom<V3<'_>> for EventClass {
+    type Error = IOFXMLError;
+
+    fn try_from(V3(element): V3) -> Result<Self, Self::Error> {
+        let id: u64 = numeric_contents(element, "Id").ok_or("Class id missing or malformed!")?;
+        let name = textual_contents(element, "ShortName")
+            .or_else(|| textual_contents(element, "Name"))
+            .ok_or("Missing class name")?;
+
+        // The fees are listed in the order they are applied, first those for the class as a whole
+        // and then those for each race.
+        let fee_ids = children(element, "Fee")
+            .chain(children(element, "RaceClass").flat_map(|race_class| children(race_class, "Fee")))
+            .filter_map(|fee| numeric_contents(fee, "Id"))
+            .collect();
+
+        Ok(EventClass { id, name, fee_ids })
+    }
+}
@@ -0,0 +1,69 @@
+use super::V3;
+use crate::iof::{numeric_contents, textual_contents, year_of_date, IOFXMLError};
+use crate::iof::{EntryFee, ValueOperator};
+use std::convert::TryFrom;
+use xmltree::Element;
+
+impl TryFrom<V3<'_>> for EntryFee {
+    type Error = IOFXMLError;
+
+    fn try_from(V3(element): V3) -> Result<Self, Self::Error> {
+        let id: u64 = numeric_contents(element, "Id").ok_or("Fee id missing or malformed!")?;
+        let name = textual_contents(element, "Name").ok_or("Fee name missing!")?;
+        let (amount, operator) = match numeric_contents::<f64>(element, "Amount") {
+            Some(amount) => (amount, ValueOperator::Fixed),
+            None => (
+                numeric_contents(element, "Percentage").ok_or("Fee has neither amount nor percentage!")?,
+                ValueOperator::Percent,
+            ),
+        };
+        let from_year_of_birth = textual_contents(element, "FromDateOfBirth")
+            .and_then(|date| year_of_date(&date));
+        let to_year_of_birth = textual_contents(element, "ToDateOfBirth")
+            .and_then(|date| year_of_date(&date));
+
+        Ok(EntryFee {
+            id,
+            card_rental: EntryFee::looks_like_card_rental(&name),
+            name,
+            amount,
+            operator,
+            from_year_of_birth,
+            to_year_of_birth,
+            event_race_id: None,
+        })
+    }
+}
+
+// IOF XML 3.0 has no list of the fees for an event. The fees are instead given in full wherever
+// they are used, in classes and entries, so all of them are collected from the document. Fees
+// given for a class in a single race of a multi-race event apply to that race only.
+pub fn entry_fees(root: &Element) -> Result<Vec<EntryFee>, IOFXMLError> {
+    let mut fees: Vec<EntryFee> = vec![];
+    collect_fees(root, None, &mut fees)?;
+    Ok(fees)
+}
+
+fn collect_fees(element: &Element, race_number: Option<u64>, fees: &mut Vec<EntryFee>) -> Result<(), IOFXMLError> {
+    for (index, child) in element.children.iter().filter_map(|node| node.as_element()).enumerate() {
+        match child.name.as_str() {
+            "Fee" => {
+                let mut fee: EntryFee = V3(child).try_into()?;
+                if !fees.iter().any(|existing| existing.id == fee.id) {
+                    fee.event_race_id = race_number;
+                    fees.push(fee);
+                }
+            }
+            "RaceClass" => {
+                let race_number = child
+                    .attributes
+                    .get("raceNumber")
+                    .and_then(|race_number| race_number.parse::<u64>().ok())
+                    .unwrap_or(index as u64 + 1);
+                collect_fees(child, Some(race_number), fees)?;
+            }
+            _ => collect_fees(child, race_number, fees)?,
+        }
+    }
+    Ok(())
+}
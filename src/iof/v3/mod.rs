@@ -0,0 +1,70 @@
+// Parsing of IOF XML 3.0, into the same types as IOF XML 2.0.3. The 2.0.3 parsers convert from the
+// serde mapping in `xml`, while the ones here implement `TryFrom<V3>` on the element tree. Use the functions in `document` to parse a whole document of either version.
+use crate::iof::{EventorTime, IOFXMLError, EVENTOR_TIME_ZONE};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use std::convert::TryFrom;
+use xmltree::Element;
+
+mod class_result;
+mod competitor;
+mod entry;
+mod event;
+mod event_class;
+mod fee;
+mod service_request;
+
+pub use class_result::class_result;
+pub use fee::entry_fees;
+
+// An element from an IOF XML 3.0 document.
+#[derive(Clone, Copy)]
+pub struct V3<'a>(pub &'a Element);
+
+pub fn subelements<'a, T: TryFrom<V3<'a>, Error = IOFXMLError>>(
+    element: &'a Element,
+    child_name: &str,
+) -> Result<Vec<T>, IOFXMLError> {
+    element
+        .children
+        .iter()
+        .filter_map(|node| node.as_element())
+        .filter(|child| child.name == child_name)
+        .map(|child| V3(child).try_into())
+        .collect()
+}
+
+fn children<'a>(element: &'a Element, child_name: &'a str) -> impl Iterator<Item = &'a Element> {
+    element
+        .children
+        .iter()
+        .filter_map(|node| node.as_element())
+        .filter(move |child| child.name == child_name)
+}
+
+// Dates and times are either given as separate Date and Time elements, or as an ISO 8601 date
+// and time such as 2022-05-01T10:00:00+02:00. Times with an offset are converted to Swedish time,
+// as in Eventor, and times without one are taken to be Swedish time already.
+fn date_time(text: &str) -> Result<EventorTime, IOFXMLError> {
+    if !text.contains('T') {
+        let date = NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .map_err(|_| "Bad date in IOF XML 3.0 date and time")?;
+        return Ok(EventorTime { date, clock: None });
+    }
+    let local = match DateTime::parse_from_rfc3339(text) {
+        Ok(time) => time.with_timezone(&EVENTOR_TIME_ZONE).naive_local(),
+        Err(_) => NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f")
+            .map_err(|_| "Bad time in IOF XML 3.0 date and time")?,
+    };
+    Ok(EventorTime { date: local.date(), clock: Some(local.time()) })
+}
+
+fn date_and_time(element: &Element) -> Result<EventorTime, IOFXMLError> {
+    let date = element
+        .get_child("Date")
+        .and_then(|date| date.get_text())
+        .ok_or("Date and time is missing the date!")?;
+    match element.get_child("Time").and_then(|time| time.get_text()) {
+        Some(time) => date_time(&format!("{}T{}", date, time)),
+        None => date_time(&date),
+    }
+}
@@ -0,0 +1,39 @@
+use super::{children, V3};
+use crate::iof::{numeric_contents, textual_contents, Competitor, IOFXMLError, ServiceRequest};
+use std::convert::TryFrom;
+
+impl TryFrom<V3<'_>> for ServiceRequest {
+    type Error = IOFXMLError;
+
+    fn try_from(V3(element): V3) -> Result<Self, Self::Error> {
+        let person_id = element
+            .get_child("Person")
+            .and_then(|person| numeric_contents(person, "Id"));
+        let request = element
+            .get_child("ServiceRequest")
+            .ok_or("Person service request is missing the service request!")?;
+        let service = request
+            .get_child("Service")
+            .ok_or("Service request is missing the service!")?;
+        let name = textual_contents(service, "Name").ok_or("Service name missing!")?;
+        let quantity: f64 = numeric_contents(request, "RequestedQuantity").unwrap_or(1f64);
+        // The fees assigned to the request are for the whole quantity.
+        let amount: f64 = children(request, "AssignedFee")
+            .filter_map(|assigned_fee| assigned_fee.get_child("Fee"))
+            .filter_map(|fee| numeric_contents::<f64>(fee, "Amount"))
+            .reduce(|total, amount| total + amount)
+            .ok_or("Service request amount missing or malformed!")?;
+
+        let person = element
+            .get_child("Person")
+            .and_then(|person| Competitor::try_from(V3(person)).ok());
+
+        Ok(ServiceRequest {
+            person_id,
+            person,
+            name,
+            quantity,
+            amount,
+        })
+    }
+}
@@ -0,0 +1,446 @@
+// A serde mapping of the IOF XML 2.0.3 documents returned by Eventor. It both reads and writes,
+// so documents can be filtered and written back, and the domain types in the parent module are
+// converted from it. Elements are named as in the standard, attributes start with @ and text
+// content is $text. Elements and attributes not listed here are skipped when reading, and so they
+// are missing from documents written back.
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct IofVersionElement {
+    #[serde(rename = "@version")]
+    pub version: String,
+}
+
+impl IofVersionElement {
+    pub fn v2_0_3() -> Option<IofVersionElement> {
+        Some(IofVersionElement { version: "2.0.3".to_string() })
+    }
+}
+
+// An attribute-only element with a value, such as CompetitorStatus.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Value {
+    #[serde(rename = "@value")]
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Time {
+    pub date: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clock: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Amount {
+    #[serde(rename = "@currency", skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    #[serde(rename = "$text")]
+    pub amount: f64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct EventList {
+    #[serde(rename = "IOFVersion", skip_serializing_if = "Option::is_none")]
+    pub iof_version: Option<IofVersionElement>,
+    #[serde(rename = "Event", default)]
+    pub events: Vec<Event>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Event {
+    #[serde(rename = "@eventForm", skip_serializing_if = "Option::is_none")]
+    pub event_form: Option<String>,
+    pub event_id: u64,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_classification_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_status_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discipline_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organiser: Option<Organiser>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<Time>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finish_date: Option<Time>,
+    #[serde(rename = "EventRace", default, skip_serializing_if = "Vec::is_empty")]
+    pub event_races: Vec<EventRace>,
+    #[serde(rename = "WebURL", skip_serializing_if = "Option::is_none")]
+    pub web_url: Option<String>,
+    #[serde(rename = "EntryBreak", default, skip_serializing_if = "Vec::is_empty")]
+    pub entry_breaks: Vec<EntryBreak>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modify_date: Option<Time>,
+}
+
+// The organisers are listed either by id only, or with the full organisation.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Organiser {
+    #[serde(rename = "OrganisationId", default, skip_serializing_if = "Vec::is_empty")]
+    pub organisation_ids: Vec<u64>,
+    #[serde(rename = "Organisation", default, skip_serializing_if = "Vec::is_empty")]
+    pub organisations: Vec<Organisation>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Organisation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organisation_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub short_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct EventRace {
+    #[serde(rename = "@raceDistance", skip_serializing_if = "Option::is_none")]
+    pub race_distance: Option<String>,
+    #[serde(rename = "@raceLightCondition", skip_serializing_if = "Option::is_none")]
+    pub race_light_condition: Option<String>,
+    pub event_race_id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub race_date: Time,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct EntryBreak {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_from_date: Option<Time>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_to_date: Option<Time>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ResultList {
+    #[serde(rename = "IOFVersion", skip_serializing_if = "Option::is_none")]
+    pub iof_version: Option<IofVersionElement>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<Event>,
+    #[serde(rename = "ClassResult", default, skip_serializing_if = "Vec::is_empty")]
+    pub class_results: Vec<ClassResult>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ClassResult {
+    pub event_class: ClassReference,
+    #[serde(rename = "PersonResult", default, skip_serializing_if = "Vec::is_empty")]
+    pub person_results: Vec<PersonResult>,
+    #[serde(rename = "TeamResult", default, skip_serializing_if = "Vec::is_empty")]
+    pub team_results: Vec<TeamResult>,
+}
+
+// The class as given in a result list.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ClassReference {
+    pub event_class_id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_short_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_race_info: Option<ClassRaceInfo>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ClassRaceInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_race_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PersonResult {
+    pub person: Person,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organisation: Option<Organisation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team_sequence: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Result>,
+    // One per stage of a multi-day event, instead of the result above.
+    #[serde(rename = "RaceResult", default, skip_serializing_if = "Vec::is_empty")]
+    pub race_results: Vec<RaceResult>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Person {
+    #[serde(rename = "@sex", skip_serializing_if = "Option::is_none")]
+    pub sex: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub person_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub person_name: Option<PersonName>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub birth_date: Option<Time>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PersonName {
+    pub family: String,
+    // One element per given name, in the order given by the sequence.
+    #[serde(default)]
+    pub given: Vec<Given>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Given {
+    #[serde(rename = "@sequence", skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<String>,
+    #[serde(rename = "$text")]
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Result {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team_sequence: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<Time>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finish_time: Option<Time>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_position: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub competitor_status: Option<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RaceResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_race_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Result>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TeamResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team_status: Option<Value>,
+    #[serde(rename = "PersonResult", default, skip_serializing_if = "Vec::is_empty")]
+    pub person_results: Vec<PersonResult>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct EntryList {
+    #[serde(rename = "IOFVersion", skip_serializing_if = "Option::is_none")]
+    pub iof_version: Option<IofVersionElement>,
+    #[serde(rename = "Entry", default)]
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Entry {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub competitor: Option<Competitor>,
+    #[serde(rename = "TeamCompetitor", default, skip_serializing_if = "Vec::is_empty")]
+    pub team_competitors: Vec<TeamCompetitor>,
+    #[serde(rename = "EntryEntryFee", default, skip_serializing_if = "Vec::is_empty")]
+    pub entry_fees: Vec<FeeReference>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry_class: Option<EntryClass>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<u64>,
+}
+
+// The person id is either given directly or as part of a full person element.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Competitor {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub person_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub person: Option<Person>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organisation: Option<Organisation>,
+    #[serde(rename = "CCard", default, skip_serializing_if = "Vec::is_empty")]
+    pub cards: Vec<CCard>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TeamCompetitor {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub person_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub person: Option<Person>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organisation: Option<Organisation>,
+    pub team_sequence: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CCard {
+    #[serde(rename = "CCardId")]
+    pub c_card_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub punching_unit_type: Option<Value>,
+}
+
+// A fee for an entry or a class, as EntryEntryFee or ClassEntryFee.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct FeeReference {
+    pub entry_fee_id: u64,
+    pub sequence: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct EntryClass {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_class_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct EntryFeeList {
+    #[serde(rename = "IOFVersion", skip_serializing_if = "Option::is_none")]
+    pub iof_version: Option<IofVersionElement>,
+    #[serde(rename = "EntryFee", default)]
+    pub entry_fees: Vec<EntryFee>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct EntryFee {
+    #[serde(rename = "@valueOperator")]
+    pub value_operator: String,
+    #[serde(rename = "@type", skip_serializing_if = "Option::is_none")]
+    pub fee_type: Option<String>,
+    pub entry_fee_id: u64,
+    pub name: String,
+    pub amount: Amount,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_date_of_birth: Option<Time>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_date_of_birth: Option<Time>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_from_date: Option<Time>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_to_date: Option<Time>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_race_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct EventClassList {
+    #[serde(rename = "IOFVersion", skip_serializing_if = "Option::is_none")]
+    pub iof_version: Option<IofVersionElement>,
+    #[serde(rename = "EventClass", default)]
+    pub event_classes: Vec<EventClass>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct EventClass {
+    pub event_class_id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_short_name: Option<String>,
+    #[serde(rename = "ClassEntryFee", default, skip_serializing_if = "Vec::is_empty")]
+    pub class_entry_fees: Vec<FeeReference>,
+}
+
+// Person service requests are either listed directly, or grouped by organisation.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ServiceRequestList {
+    #[serde(rename = "IOFVersion", skip_serializing_if = "Option::is_none")]
+    pub iof_version: Option<IofVersionElement>,
+    #[serde(rename = "PersonServiceRequest", default, skip_serializing_if = "Vec::is_empty")]
+    pub person_service_requests: Vec<PersonServiceRequest>,
+    #[serde(rename = "OrganisationServiceRequest", default, skip_serializing_if = "Vec::is_empty")]
+    pub organisation_service_requests: Vec<OrganisationServiceRequest>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OrganisationServiceRequest {
+    #[serde(rename = "Organisation", skip_serializing_if = "Option::is_none")]
+    pub organisation: Option<Organisation>,
+    #[serde(rename = "PersonServiceRequest", default, skip_serializing_if = "Vec::is_empty")]
+    pub person_service_requests: Vec<PersonServiceRequest>,
+}
+
+// The request is either a ServiceRequest element, or given directly in the person service request.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PersonServiceRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub person_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub person: Option<Person>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_request: Option<ServiceRequest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<Service>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requested_quantity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<Amount>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ServiceRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<Service>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requested_quantity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<Amount>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Service {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_id: Option<u64>,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit_price: Option<Amount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<Amount>,
+}
+
+// The members of an organisation.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct PersonList {
+    #[serde(rename = "Person", default, skip_serializing_if = "Vec::is_empty")]
+    pub persons: Vec<Person>,
+}
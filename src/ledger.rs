@@ -0,0 +1,149 @@
+use crate::billing::{BillableEvent, Category, Person, Provenance};
+use crate::dates::DateRange;
+use crate::identity::{self, Likeness, Resolver};
+use crate::iof::{Competitor, CompetitorStatus};
+use crate::roster::Roster;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::fs;
+
+// The version of the ledger file format that this version of tkassa reads.
+const VERSION: u64 = 1;
+
+// Competitions outside any Eventor, such as races abroad only found in other federations' systems,
+// kept by the club in a TOML file. Each line is billed like a line for an Eventor event.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Ledger {
+    version: u64,
+    #[serde(default)]
+    event: Vec<ExternalEvent>,
+}
+
+// One member's start at an external event.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExternalEvent {
+    date: NaiveDate,
+    name: String,
+    // The member's person id in Eventor, if known.
+    person: Option<u64>,
+    given: String,
+    family: String,
+    birth_year: Option<u64>,
+    class: Option<String>,
+    normal: f64,
+    #[serde(default)]
+    late: f64,
+    // Finished unless given, e.g. "DNS".
+    status: Option<String>,
+    url: Option<String>,
+}
+
+impl ExternalEvent {
+    fn competitor(&self) -> Competitor {
+        Competitor {
+            id: self.person,
+            given: self.given.clone(),
+            family: self.family.clone(),
+            birth_year: self.birth_year,
+        }
+    }
+
+    fn status(&self) -> CompetitorStatus {
+        self.status
+            .as_ref()
+            .map_or(CompetitorStatus::OK, |status| status.parse().unwrap_or(CompetitorStatus::Unknown))
+    }
+}
+
+impl Ledger {
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read ledger file {}: {}", path, e))?;
+        let ledger: Ledger = toml::from_str(&contents)
+            .map_err(|e| format!("Unable to parse ledger file {}: {}", path, e))?;
+        if ledger.version != VERSION {
+            return Err(format!(
+                "Ledger file {} has version {}, but only version {} is supported.",
+                path, ledger.version, VERSION
+            ));
+        }
+
+        for (index, event) in ledger.event.iter().enumerate() {
+            let line = format!("Event {} ('{}' for {} {}) in ledger file {}", index + 1, event.name, event.given, event.family, path);
+            if let Some(status) = &event.status {
+                status.parse::<CompetitorStatus>().map_err(|problem| format!("{}: {}", line, problem))?;
+            }
+            if event.normal < 0f64 || event.late < 0f64 {
+                return Err(format!("{}: fees can't be negative.", line));
+            }
+            if ledger.event[..index].iter().any(|other| {
+                other.date == event.date && other.name == event.name && identity::likeness(&other.competitor(), &event.competitor()) > Likeness::Partial
+            }) {
+                return Err(format!("{}: the member is listed twice for the event.", line));
+            }
+        }
+        Ok(ledger)
+    }
+
+    // The lines billed in the date range must agree with the members known from Eventor, so that a
+    // mistyped person id does not bill someone else. A person id is looked up in the club's roster
+    // when it has been read, and else among the persons billed for Eventor and local events. Returns
+    // every problem found.
+    pub fn validate(&self, date_range: &DateRange, persons: &[Person], roster: Option<&Roster>) -> Vec<String> {
+        self.event
+            .iter()
+            .filter(|e| e.date >= date_range.from && e.date <= date_range.to)
+            .filter_map(|event| {
+                let person_id = event.person?;
+                let known = match roster {
+                    Some(roster) => roster.member(person_id),
+                    None => persons.iter().map(|p| &p.person).find(|person| person.id == Some(person_id)),
+                };
+                let line = format!("The external event '{}' on {} is for person {} ({} {})", event.name, event.date, person_id, event.given, event.family);
+                match known {
+                    None if roster.is_some() => Some(format!("{}, who is not a member of the club in Eventor.", line)),
+                    None => Some(format!(
+                        "{}, who is not billed for any other event. Check the person id, or give --roster to check it against the club's members.",
+                        line
+                    )),
+                    Some(known) if identity::same_name(known, &event.competitor()) => None,
+                    Some(known) => Some(format!("{}, but that person is {} {} in Eventor.", line, known.given, known.family)),
+                }
+            })
+            .collect()
+    }
+
+    // Adds a line for each event in the date range to `persons`.
+    pub fn bill(&self, date_range: &DateRange, waived: &[CompetitorStatus], resolver: &mut Resolver, persons: &mut Vec<Person>) {
+        for event in self.event.iter().filter(|e| e.date >= date_range.from && e.date <= date_range.to) {
+            let status = event.status();
+            let (normal_fee, late_fee) = if waived.contains(&status) { (0f64, 0f64) } else { (event.normal, event.late) };
+            let person_index = resolver.person_index(persons, &event.competitor());
+            persons[person_index].billable.push(BillableEvent {
+                category: Category::External,
+                race_date: event.date,
+                event_name: event.name.clone(),
+                class_name: event.class.clone().unwrap_or("?".to_string()),
+                normal_fee,
+                late_fee,
+                status,
+                stages: vec![],
+                leg: None,
+                extras: vec![],
+                provenance: Provenance {
+                    event_id: 0,
+                    event_url: event.url.clone(),
+                    event_race_ids: vec![],
+                    event_class_id: 0,
+                    entry_id: None,
+                    fee_ids: vec![],
+                    cache_keys: vec![],
+                },
+                overrides: vec![],
+                paid: false,
+            });
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use crate::iof::{Entry, EntryFee, Event, EventClass, ValueOperator};
+
+// Checks the fee structure of an event for things that are likely mistakes by the organiser.
+// Returns one line of text for each problem found.
+pub fn lint_event(
+    entry_fees: &[EntryFee],
+    event_classes: &[EventClass],
+    entries: &[Entry],
+) -> Vec<String> {
+    let mut problems: Vec<String> = vec![];
+    let defined = |fee_id: &u64| entry_fees.iter().any(|fee| fee.id == *fee_id);
+
+    for class in event_classes.iter() {
+        if class.fee_ids.is_empty() {
+            problems.push(format!("Class {} has no fees.", class.name));
+            continue;
+        }
+
+        let undefined: Vec<String> = class
+            .fee_ids
+            .iter()
+            .filter(|fee_id| !defined(fee_id))
+            .map(|fee_id| fee_id.to_string())
+            .collect();
+        if !undefined.is_empty() {
+            problems.push(format!(
+                "Class {} refers to fee ids that are not defined for the event: {}.",
+                class.name,
+                undefined.join(", ")
+            ));
+            continue;
+        }
+
+        let fees: Vec<&EntryFee> = class
+            .fee_ids
+            .iter()
+            .filter_map(|fee_id| EntryFee::find(fee_id, entry_fees))
+            .collect();
+
+        // A percent fee is applied to the fixed fees before it, so there has to be at least one.
+        if let Some(first_fixed) = fees.iter().position(|fee| fee.operator == ValueOperator::Fixed) {
+            for fee in fees[..first_fixed].iter() {
+                problems.push(format!(
+                    "Class {}: percent fee '{}' comes before any fixed fee.",
+                    class.name, fee.name
+                ));
+            }
+        } else {
+            problems.push(format!(
+                "Class {} only has percent fees, with no fixed fee to apply them to.",
+                class.name
+            ));
+        }
+
+        // Fixed entry fees are expected to be alternatives for different ages. If two of them
+        // apply to the same birth year, that competitor pays both.
+        let fixed: Vec<&&EntryFee> = fees
+            .iter()
+            .filter(|fee| fee.operator == ValueOperator::Fixed && !fee.is_card_rental())
+            .collect();
+        for (index, a) in fixed.iter().enumerate() {
+            for b in fixed[index + 1..].iter() {
+                if a.event_race_id != b.event_race_id {
+                    continue;
+                }
+                let from = a.from_year_of_birth.unwrap_or(0).max(b.from_year_of_birth.unwrap_or(0));
+                let to = a.to_year_of_birth.unwrap_or(u64::MAX).min(b.to_year_of_birth.unwrap_or(u64::MAX));
+                if from <= to {
+                    problems.push(format!(
+                        "Class {}: fees '{}' and '{}' both apply to {}.",
+                        class.name,
+                        a.name,
+                        b.name,
+                        birth_years_description(from, to)
+                    ));
+                }
+            }
+        }
+    }
+
+    for entry in entries.iter() {
+        let entry_name = entry.id.map_or("?".to_string(), |id| id.to_string());
+        let undefined: Vec<String> = entry
+            .fee_ids
+            .iter()
+            .filter(|fee_id| !defined(fee_id))
+            .map(|fee_id| fee_id.to_string())
+            .collect();
+        if !undefined.is_empty() {
+            problems.push(format!(
+                "Entry {} refers to fee ids that are not defined for the event: {}.",
+                entry_name,
+                undefined.join(", ")
+            ));
+            continue;
+        }
+
+        let class = match entry
+            .event_class_id
+            .and_then(|id| event_classes.iter().find(|class| class.id == id))
+        {
+            Some(class) => class,
+            None => continue,
+        };
+        if class.fee_ids.iter().any(|fee_id| !defined(fee_id)) {
+            continue;
+        }
+
+        let entry_fee_ids: Vec<u64> = entry
+            .fee_ids
+            .iter()
+            .filter(|fee_id| !EntryFee::find(fee_id, entry_fees).is_some_and(|fee| fee.is_card_rental()))
+            .copied()
+            .collect();
+        let paid = EntryFee::paid_fees_from_fee_ids(&entry_fee_ids, entry_fees).0;
+        let candidates = class_fee_candidates(class, entry_fees);
+        if !candidates.iter().any(|candidate| (candidate - paid).abs() < 0.5) {
+            problems.push(format!(
+                "Entry {} in class {} pays {}, which does not match any fee for the class ({}).",
+                entry_name,
+                class.name,
+                paid,
+                candidates
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
+        }
+    }
+
+    if event_classes.is_empty() {
+        problems.push("The event has no classes.".to_string());
+    }
+
+    problems
+}
+
+// Events expected to have fees of about the same size: of the same classification, and either
+// single or multi-day.
+fn similar(a: &Event, b: &Event) -> bool {
+    a.classification == b.classification && a.is_multi_day() == b.is_multi_day()
+}
+
+fn describe_kind(event: &Event) -> String {
+    let classification = event.classification.map_or("unclassified".to_string(), |c| c.to_string());
+    if event.is_multi_day() {
+        format!("{} multi-day", classification)
+    } else {
+        classification
+    }
+}
+
+// Compares the fixed fees of each event with those of similar events. Any fee far from the median
+// of the fees of similar events is reported, together with the event it belongs to. Events with
+// too few similar events to compare with are not checked.
+pub fn unusual_amounts(events: &[(&Event, Vec<EntryFee>)]) -> Vec<String> {
+    let is_compared = |fee: &&EntryFee| fee.operator == ValueOperator::Fixed && !fee.is_card_rental();
+    let mut problems = vec![];
+    for (event, fees) in events.iter() {
+        let mut amounts: Vec<f64> = events
+            .iter()
+            .filter(|(other, _)| similar(event, other))
+            .flat_map(|(_, fees)| fees.iter())
+            .filter(is_compared)
+            .map(|fee| fee.amount)
+            .collect();
+        if amounts.len() < 3 {
+            continue;
+        }
+        amounts.sort_by(|a, b| a.total_cmp(b));
+        let median = amounts[amounts.len() / 2];
+
+        problems.extend(
+            fees.iter()
+                .filter(is_compared)
+                .filter(|fee| fee.amount > 3f64 * median || fee.amount < median / 3f64)
+                .map(|fee| {
+                    format!(
+                        "{}: fee '{}' of {} is unusual compared to the median fee {} of {} events.",
+                        event.name,
+                        fee.name,
+                        fee.amount,
+                        median,
+                        describe_kind(event)
+                    )
+                }),
+        );
+    }
+    problems
+}
+
+// The normal fee a direct entry in the class would pay, for each distinct age group.
+fn class_fee_candidates(class: &EventClass, entry_fees: &[EntryFee]) -> Vec<f64> {
+    let mut birth_years: Vec<u64> = vec![1900];
+    for fee in class.fee_ids.iter().filter_map(|fee_id| EntryFee::find(fee_id, entry_fees)) {
+        if let Some(year) = fee.from_year_of_birth {
+            birth_years.push(year);
+        }
+        if let Some(year) = fee.to_year_of_birth {
+            birth_years.push(year);
+            birth_years.push(year + 1);
+        }
+    }
+
+    let mut candidates: Vec<f64> = birth_years
+        .iter()
+        .map(|birth_year| {
+            let fee_ids: Vec<u64> = class
+                .direct_entry_fee_ids(birth_year, entry_fees)
+                .into_iter()
+                .filter(|fee_id| !EntryFee::find(fee_id, entry_fees).is_some_and(|fee| fee.is_card_rental()))
+                .collect();
+            EntryFee::paid_fees_from_fee_ids(&fee_ids, entry_fees).0
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.total_cmp(b));
+    candidates.dedup();
+    candidates
+}
+
+fn birth_years_description(from: u64, to: u64) -> String {
+    match (from, to) {
+        (0, u64::MAX) => "everyone".to_string(),
+        (0, to) => format!("those born {} or earlier", to),
+        (from, u64::MAX) => format!("those born {} or later", from),
+        (from, to) if from == to => format!("those born {}", from),
+        (from, to) => format!("those born {}-{}", from, to),
+    }
+}
@@ -0,0 +1,141 @@
+use crate::billing::{BillableEvent, Category, Person, Provenance, TeamFeeSplit};
+use crate::identity::Resolver;
+use crate::iof::{self, CompetitorStatus, Document};
+use crate::overrides::Overrides;
+use crate::source;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+// An event that is not in Eventor, such as a club training race timed in MeOS or OLA. It is billed
+// from the IOF XML result list exported by the timing software, with the fees given here, since
+// there are no fees in the result list.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LocalEventDefinition {
+    name: String,
+    date: NaiveDate,
+    results: String,
+    // For every class not listed in `classes`.
+    fee: f64,
+    // Fees by class name.
+    #[serde(default)]
+    classes: HashMap<String, f64>,
+    // The club's id in the timing software, when not the same as in Eventor.
+    organisation_id: Option<u64>,
+}
+
+pub struct LocalEvent<'a> {
+    definition: &'a LocalEventDefinition,
+    organisation_id: u64,
+    class_results: Vec<iof::ClassResult>,
+    // The path of the result list and the hash of its content.
+    cache_key: String,
+}
+
+impl LocalEventDefinition {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn date(&self) -> NaiveDate {
+        self.date
+    }
+}
+
+impl<'a> LocalEvent<'a> {
+    // Reads the result list, keeping only the club's own runners. This is only done for the events
+    // that are billed, so that a result list of an old event is not needed for every run.
+    pub fn load(definition: &'a LocalEventDefinition, organisation_id: u64) -> Result<LocalEvent<'a>, String> {
+        let problem = |problem: &str| format!("Unable to read the results for '{}' from {}: {}", definition.name, definition.results, problem);
+        let path = Path::new(&definition.results);
+        let cache_key = format!("{}@{}", definition.results, source::file_hash(path).map_err(|e| problem(&e.to_string()))?);
+        let document = Document::open(path).map_err(problem)?;
+        let organisation_id = definition.organisation_id.unwrap_or(organisation_id);
+        let mut class_results = iof::club_class_results(&document, organisation_id).map_err(problem)?;
+
+        // Timing software lists everyone, and those without a club are not the club's to bill.
+        let is_club = |person_result: &iof::PersonResult| person_result.organisation_id == Some(organisation_id);
+        for class_result in class_results.iter_mut() {
+            class_result.person_results.retain(is_club);
+            class_result.team_results.retain(|team| team.person_results.iter().any(is_club));
+        }
+        class_results.retain(|class_result| !class_result.person_results.is_empty() || !class_result.team_results.is_empty());
+
+        Ok(LocalEvent { definition, organisation_id, class_results, cache_key })
+    }
+
+    fn fee(&self, class_result: &iof::ClassResult) -> f64 {
+        class_result
+            .class_name
+            .as_ref()
+            .and_then(|name| self.definition.classes.get(name))
+            .copied()
+            .unwrap_or(self.definition.fee)
+    }
+
+    fn billable(&self, class_result: &iof::ClassResult, fee: (f64, f64), status: CompetitorStatus, leg: Option<u64>) -> BillableEvent {
+        BillableEvent {
+            category: Category::Local,
+            race_date: self.definition.date,
+            event_name: self.definition.name.clone(),
+            class_name: class_result.class_name.clone().unwrap_or("?".to_string()),
+            normal_fee: fee.0,
+            late_fee: fee.1,
+            status,
+            stages: vec![],
+            leg,
+            extras: vec![],
+            // There is no Eventor event, so the result list is all there is to refer to.
+            provenance: Provenance {
+                event_id: 0,
+                event_url: None,
+                event_race_ids: vec![class_result.event_race_id],
+                event_class_id: class_result.event_class_id,
+                entry_id: None,
+                fee_ids: vec![],
+                cache_keys: vec![self.cache_key.clone()],
+            },
+            overrides: vec![],
+            paid: false,
+        }
+    }
+
+    // Adds the billable lines for the club's runners to `persons`. Relay teams pay the class fee
+    // per team, divided as for Eventor events. The other overrides are applied to the lines later,
+    // but a replaced team fee must be known before it is divided.
+    pub fn bill(&self, waived: &[CompetitorStatus], team_fee_split: TeamFeeSplit, overrides: &Overrides, resolver: &mut Resolver, persons: &mut Vec<Person>) {
+        for class_result in self.class_results.iter() {
+            let fee = self.fee(class_result);
+            for person_result in class_result.person_results.iter() {
+                let status = person_result.status();
+                let paid = if waived.contains(&status) { 0f64 } else { fee };
+                let person_index = resolver.person_index(persons, &person_result.competitor);
+                persons[person_index].billable.push(self.billable(class_result, (paid, 0f64), status, None));
+            }
+
+            let class_name = class_result.class_name.clone().unwrap_or("?".to_string());
+            let replacement = overrides.fee(0, &self.definition.name, &class_name);
+            let team_fee = replacement.as_ref().map_or((fee, 0f64), |replacement| (replacement.normal, replacement.late.unwrap_or(0f64)));
+            for team in class_result.team_results.iter() {
+                let mut runners: Vec<&iof::PersonResult> = team
+                    .person_results
+                    .iter()
+                    .filter(|runner| runner.organisation_id == Some(self.organisation_id))
+                    .collect();
+                runners.sort_by_key(|runner| runner.leg);
+                let statuses: Vec<CompetitorStatus> = runners.iter().map(|runner| runner.status()).collect();
+                let shares = team_fee_split.shares(team_fee, team.person_results.len(), &statuses, waived);
+                for ((runner, status), paid) in runners.into_iter().zip(statuses).zip(shares) {
+                    let person_index = resolver.person_index(persons, &runner.competitor);
+                    let mut billable = self.billable(class_result, paid, status, runner.leg);
+                    if let Some(replacement) = replacement.as_ref().filter(|_| !waived.contains(&status)) {
+                        billable.overrides.push(replacement.note.clone());
+                    }
+                    persons[person_index].billable.push(billable);
+                }
+            }
+        }
+    }
+}
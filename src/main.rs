@@ -1,50 +1,109 @@
 use std::env;
+use std::io::{self, IsTerminal};
 use getopts::Options;
-use iof::year_from_date_string;
-use xmltree;
-use crate::iof::subelements;
+use chrono::Datelike;
 
+mod billing;
+mod config;
+mod dates;
 mod eventor;
+mod explain;
+mod export;
+mod files;
+mod filter;
+mod identity;
+mod invoice;
 mod iof;
+mod ledger;
+mod lint;
+mod local_events;
+mod output;
+mod overrides;
+mod payers;
+mod payments;
+mod roster;
+mod source;
+mod totals;
+
+use billing::{BillableEvent, Extra, ExtraKind, Person, Stage};
 
 const EVENTS: &str = "https://eventor.orientering.se/api/events";
+const EVENT: &str = "https://eventor.orientering.se/api/event/";
 const ORGANISATION_RESULTS: &str = "https://eventor.orientering.se/api/results/organisation";
 const EVENT_CLASSES: &str = "https://eventor.orientering.se/api/eventclasses";
 const ENTRIES: &str = "https://eventor.orientering.se/api/entries";
 const ENTRY_FEES: &str = "https://eventor.orientering.se/api/entryfees/events/";
+const SERVICE_REQUESTS: &str = "https://eventor.orientering.se/api/servicerequests";
+const PERSONS: &str = "https://eventor.orientering.se/api/persons/organisations/";
+
+// Dates in the tab-separated output are written as YYYYMMDD, which spreadsheets sort correctly.
+const DATE_FORMAT: &str = "%Y%m%d";
 
 fn print_usage(opts: Options) {
-    let brief = format!("Usage: tkassa [options] <API key> <from date YYYY-MM-DD> <to date YYYY-MM-DD>");
-    print!("{}", opts.usage(&brief));
+    let brief = "Usage: tkassa [options] <API key> <from date YYYY-MM-DD> <to date YYYY-MM-DD>\n       tkassa [options] <API key> <date range, e.g. 2022, 2022-Q3, \"last month\" or \"spring 2022\">\n       tkassa [options] -e <event id> <API key>\n       tkassa [options] -d <folder> <date range>";
+    print!("{}", opts.usage(brief));
 }
 
-#[derive(Debug)]
-struct BillableEvent {
-    race_date: u64,
-    event_name: String,
-    class_name: String,
-    normal_fee: f64,
-    late_fee: f64,
-    dns: bool
+// A person's races at one event, before fees are computed. There is one entry per person and
+// event, even if the person changes class between the stages of a multi-day event.
+struct Participation {
+    person_index: usize,
+    birth_year: Option<u64>,
+    races: Vec<(u64, iof::CompetitorStatus)>,
+    // The class of each race, by event race id.
+    classes: Vec<(u64, u64)>,
 }
 
-#[derive(Debug)]
-struct Person {
-    person: iof::Competitor,
-    billable: Vec<BillableEvent>,
+impl Participation {
+    fn event_class_id(&self, event_race_id: &u64) -> u64 {
+        self.classes.iter().find(|(id, _)| id == event_race_id).map_or(0, |(_, event_class_id)| *event_class_id)
+    }
 }
 
 struct DataExtractor {
     verbose: bool,
     organisation_id: u64,
+    // Empty when reading local files.
     api_key: String,
+    local_files: Option<files::LocalFiles>,
+    // Events outside Eventor, billed together with those in the date range.
+    local_events: Vec<local_events::LocalEventDefinition>,
     cache_folder: String,
-    ignore_events: Vec<u64>,
-    current_year: u64,
-    from_date: String,
-    to_date: String,
+    overrides: overrides::Overrides,
+    ledger: ledger::Ledger,
+    aliases: identity::Aliases,
+    payers: payers::Payers,
+    // How fees are shared between the member and the club, if the club has a policy.
+    share_policy: Option<totals::SharePolicy>,
+    event_filter: filter::EventFilter,
+    cancelled_policy: billing::CancelledPolicy,
+    update: bool,
+    iof_version: iof::IofVersion,
+    event_id: Option<u64>,
+    // Not set when a single event is given.
+    date_range: Option<dates::DateRange>,
     include_class_names: bool,
     show_only_starts: bool,
+    waived_statuses: Vec<iof::CompetitorStatus>,
+    team_fee_split: billing::TeamFeeSplit,
+    // Empty unless the club lists them, and card rental is then guessed from the fee names.
+    card_rental_fees: Vec<String>,
+    include_services: bool,
+    roster: bool,
+    all_members: bool,
+    lint: bool,
+    export: Option<export::Export>,
+    // The folder to write a result list per event to, when exporting the results of several events.
+    export_folder: Option<String>,
+    explain_person_id: Option<u64>,
+    format: output::Format,
+    // The folder to write invoices to, if any.
+    invoices: Option<String>,
+    // Whether the end of the date range is remembered as the last day billed.
+    record: bool,
+    invoice_settings: invoice::InvoiceSettings,
+    // Payments to match to the invoices, instead of billing.
+    payments: Option<Vec<payments::Payment>>,
 }
 
 impl DataExtractor {
@@ -56,82 +115,313 @@ impl DataExtractor {
             Ok(matches) => { 
                 if matches.opt_present("h") {
                     Err(None)
-                } else if matches.free.len() < 3 {
+                } else if matches.free.len() < usize::from(!matches.opt_present("d")) + usize::from(!matches.opt_present("e")) {
                     Err(Some("Too few arguments.".to_string()))
-                } else if matches.free[1].len() < 4 {
-                    Err(Some("Starting date is too short.".to_string()))
                 } else {
+                    // Settings from the club config file are used unless given on the command line.
+                    let config = match matches.opt_str("f") {
+                        None => config::Config::default(),
+                        Some(path) => match config::Config::from_file(&path) {
+                            Ok(config) => config,
+                            Err(problem) => return Err(Some(problem)),
+                        },
+                    };
                     let verbose = !matches.opt_present("q");
                     let show_only_starts = matches.opt_present("s");
-                    let cache_folder = matches.opt_str("c").unwrap_or("caches".to_string());
+                    let cache_folder = matches.opt_str("c").or(config.cache).unwrap_or("caches".to_string());
                     let organisation_id = matches
                         .opt_str("o")
-                        .map(|v| v.parse::<u64>().ok())
-                        .flatten()
+                        .and_then(|v| v.parse::<u64>().ok())
+                        .or(config.organisation_id)
                         .unwrap_or(224); // Kungälvs OK
-                    let api_key = (&matches.free[0]).to_string();
-                    let from_date = (&matches.free[1]).to_string();
-                    let to_date = (&matches.free[2]).to_string();
+                    // No API key is needed for local files.
+                    let local_files = match matches.opt_str("d").map(|folder| files::LocalFiles::new(&folder, verbose)) {
+                        None => None,
+                        Some(Ok(local_files)) => Some(local_files),
+                        Some(Err(problem)) => return Err(Some(problem)),
+                    };
+                    let (api_key, dates) = match local_files {
+                        Some(_) => (String::new(), &matches.free[..]),
+                        None => (matches.free[0].to_string(), &matches.free[1..]),
+                    };
+                    // A single event can be given instead of a date range.
+                    let event_id = match matches.opt_str("e").map(|v| v.parse::<u64>()) {
+                        None => None,
+                        Some(Ok(event_id)) => Some(event_id),
+                        Some(Err(_)) => return Err(Some("Invalid event id.".to_string())),
+                    };
+                    let calendar = dates::Calendar::new(config.season, &cache_folder);
+                    let date_range = match (event_id, dates.first(), dates.get(1)) {
+                        (Some(_), _, _) | (None, None, _) => None,
+                        (None, Some(range), None) => Some(calendar.range(range)),
+                        (None, Some(from), Some(to)) => Some(calendar.between(from, to)),
+                    };
+                    let date_range = match date_range.transpose() {
+                        Ok(date_range) => date_range,
+                        Err(problem) => return Err(Some(problem)),
+                    };
+                    let lint = matches.opt_present("l");
+                    let export = match matches.opt_str("export").map(|v| v.parse::<export::Export>()) {
+                        None => None,
+                        Some(Ok(export)) => Some(export),
+                        Some(Err(problem)) => return Err(Some(problem.to_string())),
+                    };
+                    let export_folder = matches.opt_str("export-folder");
+                    if export_folder.is_some() && export != Some(export::Export::Results) {
+                        return Err(Some("--export-folder is only for --export results.".to_string()));
+                    }
+                    let explain_person_id = match matches.opt_str("x").map(|v| v.parse::<u64>()) {
+                        None => None,
+                        Some(Ok(person_id)) => Some(person_id),
+                        Some(Err(_)) => return Err(Some("Invalid person id.".to_string())),
+                    };
+                    if explain_person_id.is_some() && event_id.is_none() {
+                        return Err(Some("An event (-e) is needed to explain fees.".to_string()));
+                    }
                     let include_class_names = matches.opt_present("n");
-                    let ignore_events: Vec<u64> = matches
-                        .opt_str("i")
-                        .unwrap_or("".to_string())
-                        .split(",")
-                        .filter_map(|p| p.parse::<u64>().ok())
-                        .collect();
-                    match year_from_date_string(&from_date) {
-                        None => Err(Some("Invalid starting year.".to_string())),
-                        Some(current_year) => Ok( DataExtractor {
-                            verbose, organisation_id, api_key,
-                            cache_folder, ignore_events,
-                            current_year, from_date, to_date,
-                            include_class_names, show_only_starts
-                        }),
+                    let include_services = matches.opt_present("r") || config.services;
+                    let card_rental_fees = config.card_rental_fees.iter().map(|name| name.trim().to_lowercase()).collect();
+                    let all_members = matches.opt_present("all-members");
+                    let roster = matches.opt_present("roster") || config.roster || all_members;
+                    // -j is short for --format json.
+                    let format = match matches
+                        .opt_present("j")
+                        .then(|| "json".to_string())
+                        .or(matches.opt_str("format"))
+                        .or(config.format)
+                        .unwrap_or("tsv".to_string())
+                        .parse::<output::Format>() {
+                            Ok(format) => format,
+                            Err(problem) => return Err(Some(problem.to_string())),
+                        };
+                    let invoices = matches.opt_str("invoices");
+                    let invoice_settings = config.invoice.unwrap_or_default();
+                    let mut payments: Option<Vec<payments::Payment>> = None;
+                    for path in matches.opt_strs("payments").iter() {
+                        match payments::from_file(path) {
+                            Ok(mut file_payments) => payments.get_or_insert(vec![]).append(&mut file_payments),
+                            Err(problem) => return Err(Some(problem)),
+                        }
+                    }
+                    if payments.is_some() && format != output::Format::Tsv && format != output::Format::Json {
+                        return Err(Some("The payment report is written as tsv or json.".to_string()));
+                    }
+                    if (invoices.is_some() || payments.is_some()) && matches.opt_present("e") {
+                        return Err(Some("Invoices are for a date range, not a single event.".to_string()));
+                    }
+                    // Writing invoices is a billing. Reading local files, e.g. to audit an old season, is not.
+                    let record = matches.opt_present("record") || invoices.is_some();
+                    if record && matches.opt_present("e") {
+                        return Err(Some("Only a date range can be recorded as billed, not a single event.".to_string()));
+                    }
+                    let record = record && local_files.is_none();
+                    if format.is_binary() && io::stdout().is_terminal() {
+                        return Err(Some("XLSX output is binary, redirect it to a file, e.g. > billing.xlsx".to_string()));
+                    }
+                    let overrides = match matches.opt_str("m").or(config.overrides) {
+                        None => overrides::Overrides::default(),
+                        Some(path) => match overrides::Overrides::from_file(&path) {
+                            Ok(overrides) => overrides,
+                            Err(problem) => return Err(Some(problem)),
+                        },
+                    };
+                    let ledger = match matches.opt_str("ledger").or(config.ledger) {
+                        None => ledger::Ledger::default(),
+                        Some(path) => match ledger::Ledger::from_file(&path) {
+                            Ok(ledger) => ledger,
+                            Err(problem) => return Err(Some(problem)),
+                        },
+                    };
+                    let aliases = match matches.opt_str("aliases").or(config.aliases) {
+                        None => identity::Aliases::default(),
+                        Some(path) => match identity::Aliases::from_file(&path) {
+                            Ok(aliases) => aliases,
+                            Err(problem) => return Err(Some(problem)),
+                        },
+                    };
+                    let payers = match matches.opt_str("payers").or(config.payers) {
+                        None => payers::Payers::default(),
+                        Some(path) => match payers::Payers::from_file(&path) {
+                            Ok(payers) => payers,
+                            Err(problem) => return Err(Some(problem)),
+                        },
+                    };
+                    let share_policy = config.share;
+                    if let Some(Err(problem)) = share_policy.map(|policy| policy.validate()) {
+                        return Err(Some(problem));
                     }
+                    let waived_statuses = match matches
+                        .opt_str("w")
+                        .unwrap_or(config.waive.join(","))
+                        .split(',')
+                        .filter(|p| !p.is_empty())
+                        .map(|p| p.parse::<iof::CompetitorStatus>())
+                        .collect::<Result<Vec<iof::CompetitorStatus>, &str>>() {
+                            Ok(statuses) => statuses,
+                            Err(problem) => return Err(Some(problem.to_string())),
+                        };
+                    let team_fee_split = match matches
+                        .opt_str("t")
+                        .or(config.team_fees)
+                        .unwrap_or("equal".to_string())
+                        .parse::<billing::TeamFeeSplit>() {
+                            Ok(split) => split,
+                            Err(problem) => return Err(Some(problem.to_string())),
+                        };
+                    let cancelled_policy = match matches
+                        .opt_str("cancelled")
+                        .or(config.cancelled)
+                        .unwrap_or("waive".to_string())
+                        .parse::<billing::CancelledPolicy>() {
+                            Ok(policy) => policy,
+                            Err(problem) => return Err(Some(problem.to_string())),
+                        };
+                    let update = matches.opt_present("u");
+                    let iof_version = match matches
+                        .opt_str("iof")
+                        .or(config.iof_version)
+                        .unwrap_or("2.0.3".to_string())
+                        .parse::<iof::IofVersion>() {
+                            Ok(version) => version,
+                            Err(problem) => return Err(Some(problem.to_string())),
+                        };
+                    if export.is_some() && iof_version != iof::IofVersion::V2_0_3 {
+                        return Err(Some("Only IOF XML 2.0.3 can be exported.".to_string()));
+                    }
+                    let mut event_filter = filter::EventFilter { include: config.include, exclude: config.exclude };
+                    for (option, rules) in [("include", &mut event_filter.include), ("exclude", &mut event_filter.exclude)] {
+                        for rule in matches.opt_strs(option).iter() {
+                            match rule.parse::<filter::Rule>() {
+                                Ok(rule) => rules.push(rule),
+                                Err(problem) => return Err(Some(problem.to_string())),
+                            }
+                        }
+                    }
+                    Ok( DataExtractor {
+                        verbose, organisation_id, api_key, local_files, local_events: config.local_event,
+                        cache_folder, overrides, ledger, aliases, payers, share_policy, event_filter,
+                        cancelled_policy, update, iof_version,
+                        event_id, date_range,
+                        include_class_names, show_only_starts,
+                        waived_statuses, team_fee_split, card_rental_fees,
+                        include_services, roster, all_members, lint, export, export_folder,
+                        explain_person_id, format, invoices, record, invoice_settings, payments,
+                    })
                 }
             },
-            Err(f) => { return Err(Some(format!("Unable to parse command-line options: {:?}", f.to_string()))) }
+            Err(f) => Err(Some(format!("Unable to parse command-line options: {:?}", f.to_string())))
+        }
+    }
+
+    // The IOF XML files given with -d, or else Eventor.
+    fn source(&self) -> Box<dyn source::Source + '_> {
+        match &self.local_files {
+            Some(local_files) => Box::new(local_files),
+            None => Box::new(eventor::EventorClient::new(&self.api_key, &self.cache_folder, self.verbose)
+                .with_iof_version(self.iof_version)),
         }
     }
 
-    fn get_number_of_starts(&self) -> usize {
-        let eventor_client = eventor::EventorClient::new(&self.api_key, &self.cache_folder, self.verbose); 
-            
-        let event_list = eventor_client.request(EVENTS, 
-            &[("fromDate", self.from_date.as_str()), 
-                        ("toDate", self.to_date.as_str())]);
+    // The year used for members without a known birth date.
+    fn current_year(&self, event: &iof::Event) -> u64 {
+        let year = self.date_range
+            .map(|range| range.from.year())
+            .or(event.first_race_date().map(|date| date.year()))
+            .unwrap_or(dates::today().year());
+        year as u64
+    }
 
-        let mut events: Vec<iof::Event> = subelements(&event_list, "Event")
-            .expect("XML parsing error when reading event list");
+    // All events in the date range, or the single event asked for, sorted by date and without those ignored.
+    // Also returns the cache key of the response.
+    fn events(&self, source: &dyn source::Source) -> Result<(Vec<iof::Event>, String), String> {
+        // The events carry the dates they were last changed, so fetching them again is enough to
+        // find out which of the other cached responses are stale.
+        let updated_since = if self.update { Some(std::time::SystemTime::now()) } else { None };
+        let (mut events, cache_key): (Vec<iof::Event>, String) = match self.event_id {
+            Some(event_id) => {
+                let event_url: String = EVENT.to_owned() + &event_id.to_string();
+                let (event_element, cache_key) = source.request_modified_since(&event_url, &[("eventId", &event_id.to_string())], updated_since)?;
+                (vec![iof::event(&event_element).map_err(xml_error("event", &cache_key))?], cache_key)
+            },
+            None => {
+                let date_range = self.date_range.expect("A date range is needed without an event.");
+                let (event_list, cache_key) = source.request_modified_since(EVENTS, 
+                    &[("fromDate", &date_range.from.to_string()), 
+                                ("toDate", &date_range.to.to_string())], updated_since)?;
 
+                (iof::events(&event_list)
+                    .map_err(xml_error("event list", &cache_key))?, cache_key)
+            },
+        };
 
         events.sort_by_key(|e| e.first_race_date());
-        let mut total_number_of_starts = 0;
-
-        for event in events.iter_mut() {
-            if self.ignore_events.contains(&event.id) {
-                continue;
+        let mut included: Vec<iof::Event> = vec![];
+        for event in events.into_iter() {
+            let eventor_fees = if self.event_filter.needs_eventor_fees() {
+                Some(!self.entry_fees(source, &event)?.0.is_empty())
+            } else {
+                None
+            };
+            if self.event_filter.includes(&event, eventor_fees) {
+                included.push(event);
             }
+        }
+        let mut events = included;
+        if self.cancelled_policy == billing::CancelledPolicy::Skip {
+            events.retain(|event| {
+                if event.is_cancelled() && self.verbose {
+                    eprintln!("Skipping event '{}', which has been cancelled.", event.name);
+                }
+                !event.is_cancelled()
+            });
+        }
+        events.retain(|event| match self.overrides.exclude_reason(event.id, &event.name) {
+            None => true,
+            Some(reason) => {
+                if self.verbose {
+                    eprintln!("Excluding event '{}'. {}", event.name, reason);
+                }
+                false
+            },
+        });
+        Ok((events, cache_key))
+    }
+
+    // The club's members in Eventor.
+    fn roster(&self, source: &dyn source::Source) -> Result<roster::Roster, String> {
+        let updated_since = if self.update { Some(std::time::SystemTime::now()) } else { None };
+        let persons_url: String = PERSONS.to_owned() + &self.organisation_id.to_string();
+        let (person_list, cache_key) = source.request_modified_since(&persons_url, &[("includeContactDetails", "false")], updated_since)?;
+        Ok(roster::Roster::new(iof::persons(&person_list).map_err(xml_error("the club's members", &cache_key))?))
+    }
 
-            let result_list = eventor_client.request(ORGANISATION_RESULTS, 
+    fn get_number_of_starts(&self) -> Result<usize, String> {
+        let source = self.source();
+        let mut total_number_of_starts = 0;
+
+        for event in self.events(source.as_ref())?.0.iter() {
+            let (result_list, result_list_cache_key) = self.event_request(source.as_ref(), event, ORGANISATION_RESULTS, 
                 &[("organisationIds", &self.organisation_id.to_string()), 
-                            ("eventId", &event.id.to_string())]);
+                            ("eventId", &event.id.to_string())])?;
 
             // First we just check that it contains any ClassResult. If not, then noone from our club was at
             // the event (and were not pre-entered either).
-            if result_list.get_child("ClassResult").is_none() {
+            if !result_list.has_element("ClassResult") {
                 continue
             }
 
-            let class_results: Vec<iof::ClassResult> = subelements(&result_list, "ClassResult")
-            .expect("XML parsing error when reading result list");
+            let class_results: Vec<iof::ClassResult> = iof::club_class_results(&result_list, self.organisation_id)
+            .map_err(xml_error("result list", &result_list_cache_key))?;
             let mut num_starts_at_this_event = 0;
 
             for class in class_results.iter() {
-                for person_result in class.person_results.iter() {
-                    if !person_result.dns {
-                        num_starts_at_this_event += 1;
-                    }
+                let team_runners = class.team_results.iter().flat_map(|team| team.person_results.iter());
+                for person_result in class.person_results.iter().chain(team_runners) {
+                    // One start per stage for multi-day events.
+                    num_starts_at_this_event += person_result.race_statuses
+                        .iter()
+                        .filter(|race_status| race_status.status.started())
+                        .count();
                 }
             }
             // if self.verbose {
@@ -140,142 +430,637 @@ impl DataExtractor {
             total_number_of_starts += num_starts_at_this_event;
         }
 
-        total_number_of_starts
+        Ok(total_number_of_starts)
     }
 
-    fn run(&self) {
-        let eventor_client = eventor::EventorClient::new(&self.api_key, &self.cache_folder, self.verbose); 
-            
-        let event_list = eventor_client.request(EVENTS, 
-            &[("fromDate", self.from_date.as_str()), 
-                        ("toDate", self.to_date.as_str())]);
+    // Reports suspicious fee structures, either for the single event given or for all events in the
+    // date range where someone from the club took part.
+    fn lint(&self) -> Result<(), String> {
+        let source = self.source();
+        let (events, _) = self.events(source.as_ref())?;
 
-        let mut events: Vec<iof::Event> = subelements(&event_list, "Event")
-            .expect("XML parsing error when reading event list");
+        let mut linted: Vec<(&iof::Event, Vec<iof::EntryFee>)> = vec![];
+        for event in events.iter() {
+            if self.event_id.is_none() {
+                let (result_list, _) = self.event_request(source.as_ref(), event, ORGANISATION_RESULTS, 
+                    &[("organisationIds", &self.organisation_id.to_string()), 
+                                ("eventId", &event.id.to_string())])?;
+                if !result_list.has_element("ClassResult") {
+                    continue
+                }
+            }
 
-        events.sort_by_key(|e| e.first_race_date());
+            let (entry_fees, _) = self.entry_fees(source.as_ref(), event)?;
+            let (class_list, class_list_cache_key) = self.event_request(source.as_ref(), event, EVENT_CLASSES, 
+            &[("includeEntryFees", "true"), ("eventId", &event.id.to_string())])?;
+            let event_classes: Vec<iof::EventClass> = iof::event_classes(&class_list)
+                .map_err(xml_error("event classes", &class_list_cache_key))?;
+            let (entry_list, entry_list_cache_key) = self.event_request(source.as_ref(), event, ENTRIES, 
+            &[("includeEntryFees", "true"), 
+                        ("organisationIds", &self.organisation_id.to_string()), 
+                        ("eventIds", &event.id.to_string())])?;
+            let entries: Vec<iof::Entry> = iof::club_entries(&entry_list, self.organisation_id)
+                .map_err(xml_error("entry list", &entry_list_cache_key))?;
 
-        let mut persons: Vec<Person> = vec![];
-        for event in events.iter_mut() {
-            if self.ignore_events.contains(&event.id) {
-                continue;
+            let problems = lint::lint_event(&entry_fees, &event_classes, &entries);
+            println!("{} ({}, {})", event.name, event.id, event.first_race_date().map_or("?".to_string(), |date| date.to_string()));
+            if problems.is_empty() {
+                println!("\tNo problems found.");
             }
-            // Get the result list. Will be read in more detail later. 
-            let result_list = eventor_client.request(ORGANISATION_RESULTS, 
-                &[("organisationIds", &self.organisation_id.to_string()), 
-                            ("eventId", &event.id.to_string())]);
+            for problem in problems.iter() {
+                println!("\t{}", problem);
+            }
+            linted.push((event, entry_fees));
+        }
 
-            // First we just check that it contains any ClassResult. If not, then noone from our club was at
-            // the event (and were not pre-entered either).
-            if result_list.get_child("ClassResult").is_none() {
+        let unusual = lint::unusual_amounts(&linted);
+        if !unusual.is_empty() {
+            println!("Unusual amounts:");
+            for problem in unusual.iter() {
+                println!("\t{}", problem);
+            }
+        }
+        Ok(())
+    }
+
+    // Writes the club's results or entries for the events as IOF XML 2.0.3, after the event filters
+    // and overrides have been applied.
+    fn export(&self, export: export::Export) -> Result<(), String> {
+        let source = self.source();
+        let (events, _) = self.events(source.as_ref())?;
+
+        let mut result_lists: Vec<(u64, iof::xml::ResultList)> = vec![];
+        let mut entries: Vec<iof::xml::Entry> = vec![];
+        for event in events.iter() {
+            let (document, cache_key) = match export {
+                export::Export::Results => self.event_request(source.as_ref(), event, ORGANISATION_RESULTS, 
+                    &[("organisationIds", &self.organisation_id.to_string()), 
+                                ("eventId", &event.id.to_string())])?,
+                export::Export::Entries => self.event_request(source.as_ref(), event, ENTRIES, 
+                    &[("includeEntryFees", "true"), 
+                                ("organisationIds", &self.organisation_id.to_string()), 
+                                ("eventIds", &event.id.to_string())])?,
+            };
+            // Cached responses may still be in IOF XML 3.0.
+            if document.version() != iof::IofVersion::V2_0_3 {
+                if self.verbose {
+                    eprintln!("Event '{}' is not in IOF XML 2.0.3 and is left out.", event.name);
+                }
                 continue
             }
 
-            if self.verbose {
-                println!("Event '{}'", event.name);
+            match export {
+                export::Export::Results => {
+                    if !document.has_element("ClassResult") {
+                        continue
+                    }
+                    let mut result_list: iof::xml::ResultList = document.deserialize()
+                        .map_err(xml_error("result list", &cache_key))?;
+                    if result_list.event.is_none() {
+                        result_list.event_id.get_or_insert(event.id);
+                    }
+                    export::club_results(&mut result_list, self.organisation_id);
+                    result_lists.push((event.id, result_list));
+                },
+                export::Export::Entries => {
+                    let entry_list: iof::xml::EntryList = document.deserialize()
+                        .map_err(xml_error("entry list", &cache_key))?;
+                    for mut entry in entry_list.entries.into_iter().filter(|entry| iof::is_club_entry(entry, self.organisation_id)) {
+                        entry.event_id.get_or_insert(event.id);
+                        entries.push(entry);
+                    }
+                },
             }
+        }
 
-            // Get entry fees.
-            let entry_fee_url: String = ENTRY_FEES.to_owned() + &event.id.to_string(); // & format!(ENTRY_FEES, event.id);
-            let entry_fee_list: xmltree::Element = eventor_client.request(entry_fee_url, &[("eventId", &event.id.to_string())]);
-            let entry_fees: Vec<iof::EntryFee> = iof::subelements(&entry_fee_list, "EntryFee")
-                .expect("XML parsing error when reading entry fee list");
-
-            // Get event classes
-            let class_list: xmltree::Element = eventor_client.request(EVENT_CLASSES, 
-            &[("includeEntryFees", "true"), ("eventId", &event.id.to_string())]);
-            let event_classes: Vec<iof::EventClass> = iof::subelements(&class_list, "EventClass")
-                .expect("XML parsing error when reading event classes");
-            
-            // Get pre-entries
-            let entry_list: xmltree::Element = eventor_client.request(ENTRIES, 
-            &[("includeEntryFees", "true"), 
-                        ("organisationIds", &self.organisation_id.to_string()), 
-                        ("eventIds", &event.id.to_string())]);
-            let entries: Vec<iof::Entry> = iof::subelements(&entry_list, "Entry")
-                .expect("XML parsing error while reading entry list");
+        match (export, &self.export_folder) {
+            (export::Export::Results, Some(folder)) => {
+                let count = export::write_result_lists(result_lists, folder)?;
+                eprintln!("Wrote {} result lists to {}.", count, folder);
+            }
+            // A result list is for a single event.
+            (export::Export::Results, None) => match result_lists.pop() {
+                Some((_, result_list)) if result_lists.is_empty() => print!("{}", export::result_list(result_list)),
+                Some(_) => return Err("The results of several events are written one file per event, to the folder given with --export-folder.".to_string()),
+                None => print!("{}", export::result_list(iof::xml::ResultList::default())),
+            },
+            (export::Export::Entries, _) => print!("{}", export::entry_list(entries)),
+        }
+        Ok(())
+    }
 
-            let class_results: Vec<iof::ClassResult> = subelements(&result_list, "ClassResult")
-                .expect("XML parsing error when reading result list");
+    fn run(&self) -> Result<(), String> {
+        let source = self.source();
 
-            // for each result
-            for class in class_results.iter() {
-                let race_date = event.date_for_race(&class.event_race_id);
-                // We are not guaranteed to find the event class, if the entry classes are different from the race
-                // classes (such as for elite events with qualifications).
-                let event_class = event_classes.iter().find(|event_class| event_class.id == class.event_class_id);
-
-                for person_result in class.person_results.iter() {
-                    // find reference to person in persons, or create new.
-                    let mut existing_person = persons
-                        .iter_mut()
-                        .find(|x| x.person == person_result.competitor);
-                    if existing_person.is_none() {
-                        existing_person = match persons
-                            .iter_mut()
-                            .find(|x| x.person.probably_the_same_as(&person_result.competitor)) 
-                        {
-                            Some(p) => Some(p),
-                            None => {
-                                persons.push(Person { person: person_result.competitor.clone(), billable: vec![] });
-                                persons.last_mut()
-                            },
-                        };
-                    }
-                    let existing_person = existing_person.unwrap();
-                    
-                    let paid = 
-                    // Is this person pre-registered?
-                    if let Some(entry) = entries.iter().find(|entry| entry.is_for_person(&existing_person.person.id)){
-                        // Yes.
-                        entry.paid_fees(&entry_fees)
-                    } else if let Some(event_class) = event_class {
-                        // No? Ok. Then we get the class id, and the fees from there.
-                    event_class.paid_direct_entry_fees(
-                            & person_result.competitor.birth_year.unwrap_or(self.current_year), 
-                            &entry_fees)
-                    } else {
-                        panic!("The person was not pre-registered and the class id is unknown.");
-                    };
+        let roster = if self.roster { Some(self.roster(source.as_ref())?) } else { None };
+        let mut resolver = identity::Resolver::new(&self.aliases, roster.as_ref());
+        let mut persons: Vec<Person> = vec![];
+        let (events, event_list_cache_key) = self.events(source.as_ref())?;
+        for event in events.iter() {
+            self.bill_event(source.as_ref(), event, &event_list_cache_key, roster.as_ref(), &mut resolver, &mut persons)?;
+        }
+        if let Some(date_range) = self.date_range {
+            for definition in self.local_events.iter().filter(|e| e.date() >= date_range.from && e.date() <= date_range.to) {
+                if self.verbose {
+                    eprintln!("Local event '{}'", definition.name());
+                }
+                let local_event = local_events::LocalEvent::load(definition, self.organisation_id)?;
+                local_event.bill(&self.waived_statuses, self.team_fee_split, &self.overrides, &mut resolver, &mut persons);
+            }
+            let problems = self.ledger.validate(&date_range, &persons, roster.as_ref());
+            if !problems.is_empty() {
+                return Err(format!("The ledger cannot be billed:\n{}", problems.join("\n")));
+            }
+            self.ledger.bill(&date_range, &self.waived_statuses, &mut resolver, &mut persons);
 
-                    existing_person.billable.push(
-                        BillableEvent { 
-                            race_date: race_date.date , 
-                            event_name: event.name.clone(),
-                            class_name: event_class.map_or("?".to_string(), |c| c.name.clone()),
-                            normal_fee: paid.0, 
-                            late_fee: paid.1, 
-                            dns: person_result.dns 
+            // Local events and the ledger have no Eventor id, so the overrides give them by name.
+            let local = |b: &BillableEvent| b.category != billing::Category::Eventor;
+            for p in persons.iter_mut() {
+                p.billable.retain(|b| match self.overrides.exclude_reason(b.provenance.event_id, &b.event_name) {
+                    Some(reason) if local(b) => {
+                        if self.verbose {
+                            eprintln!("Excluding event '{}' for {} {}. {}", b.event_name, p.person.given, p.person.family, reason);
                         }
-                    );
+                        false
+                    },
+                    _ => true,
+                });
+                let person_id = p.person.id;
+                for billable in p.billable.iter_mut().filter(|b| local(b)) {
+                    self.overrides.apply(billable, person_id, &self.waived_statuses, false);
                 }
             }
+            persons.retain(|p| !p.billable.is_empty());
+        }
+        if let Some(roster) = &roster {
+            roster.apply(&mut persons, self.all_members);
+            resolver.warnings.append(&mut self.payers.validate(roster));
+        }
+        for warning in resolver.warnings.iter() {
+            eprintln!("Warning: {}", warning);
+        }
+        // The explanation has already been printed while billing, except for the manual corrections.
+        if let Some(person_id) = self.explain_person_id {
+            match persons.iter().find(|p| p.person.id == Some(person_id)) {
+                None => println!("Person {} has no result at the event.", person_id),
+                Some(p) => {
+                    for b in p.billable.iter().filter(|b| !b.overrides.is_empty()) {
+                        println!("Corrected by the overrides file: {}", b.overrides.join("; "));
+                        println!("  Normal fee: {}", b.normal_fee + b.stages.iter().map(|s| s.normal_fee).sum::<f64>());
+                        println!("  Late fee: {}", b.late_fee + b.stages.iter().map(|s| s.late_fee).sum::<f64>());
+                    }
+                },
+            }
+            return Ok(());
         }
 
         // Present the results, sorted by last name.
         persons.sort_by_key(|person| person.person.family.clone());
-
+        let mut grand_totals = totals::Totals::default();
         for p in persons.iter_mut() {
-            let id: String = match p.person.id {
-                Some(i) => i.to_string(),
-                None => "????".to_string(),
+            p.billable.sort_by_key(|b| b.race_date);
+            p.totals = totals::Totals::of(&p.billable, self.share_policy.as_ref());
+            grand_totals += p.totals;
+        }
+
+        let report = output::Report {
+            persons: &persons,
+            households: if self.payers.is_empty() { vec![] } else { self.payers.households(&persons) },
+            totals: grand_totals,
+            shares: self.share_policy.is_some(),
+            include_class_names: self.include_class_names,
+        };
+        if let (Some(folder), Some(date_range)) = (&self.invoices, &self.date_range) {
+            let invoices = invoice::invoices(&report, date_range, &self.invoice_settings)?;
+            match invoice::write(&invoices, &self.invoice_settings, folder, self.verbose) {
+                Ok(count) => eprintln!("Wrote {} invoices to {}.", count, folder),
+                Err(problem) => return Err(problem),
+            }
+        }
+        let written = if let (Some(payments), Some(date_range)) = (&self.payments, &self.date_range) {
+            let invoices = invoice::invoices(&report, date_range, &self.invoice_settings)?;
+            let reconciliation = payments::reconcile(&invoices, payments);
+            if self.format == output::Format::Json {
+                serde_json::to_writer_pretty(io::stdout().lock(), &reconciliation).map_err(io::Error::from)
+            } else {
+                reconciliation.write_text(&mut io::stdout().lock())
+            }
+        } else {
+            output::output(self.format).write(&report, &mut io::stdout().lock())
+        };
+        if let Err(problem) = written {
+            return Err(format!("Unable to write the output: {}", problem));
+        }
+        // Only a billing run is remembered, and only once everything has been written.
+        if let (true, Some(date_range)) = (self.record, self.date_range) {
+            dates::record_billing(&self.cache_folder, &date_range);
+        }
+        Ok(())
+    }
+
+    // A request for data about the event. Cached responses from before the event was last changed in
+    // Eventor are not used.
+    fn event_request(&self, source: &dyn source::Source, event: &iof::Event, 
+        url: &str, parameters: &[(&str, &str)]) -> Result<(iof::Document, String), String> {
+        source.request_modified_since(url, parameters, event.modify_date.map(|date| date.to_system_time()))
+    }
+
+    // The entry fees defined for the event in Eventor, and the cache key of the response.
+    fn entry_fees(&self, source: &dyn source::Source, event: &iof::Event) -> Result<(Vec<iof::EntryFee>, String), String> {
+        let entry_fee_url: String = ENTRY_FEES.to_owned() + &event.id.to_string(); // & format!(ENTRY_FEES, event.id);
+        let (entry_fee_list, cache_key) = self.event_request(source, event, &entry_fee_url, &[("eventId", &event.id.to_string())])?;
+        let mut entry_fees = iof::entry_fees(&entry_fee_list).map_err(xml_error("entry fee list", &cache_key))?;
+        if !self.card_rental_fees.is_empty() {
+            for fee in entry_fees.iter_mut() {
+                fee.card_rental = self.card_rental_fees.contains(&fee.name.trim().to_lowercase());
+            }
+        }
+        Ok((entry_fees, cache_key))
+    }
+
+    // Adds the billable lines for everyone from the club at the event to `persons`.
+    fn bill_event(&self, source: &dyn source::Source, event: &iof::Event, event_list_cache_key: &str, 
+        roster: Option<&roster::Roster>, resolver: &mut identity::Resolver, persons: &mut Vec<Person>) -> Result<(), String> {
+        // Birth dates in the roster are trusted over those in the result list.
+        let birth_year = |competitor: &iof::Competitor| roster.map_or(competitor.birth_year, |roster| roster.birth_year(competitor));
+        // Get the result list. Will be read in more detail later. 
+        let (result_list, result_list_cache_key) = self.event_request(source, event, ORGANISATION_RESULTS, 
+            &[("organisationIds", &self.organisation_id.to_string()), 
+                        ("eventId", &event.id.to_string())])?;
+
+        // First we just check that it contains any ClassResult. If not, then noone from our club was at
+        // the event (and were not pre-entered either).
+        if !result_list.has_element("ClassResult") {
+            return Ok(())
+        }
+
+        if self.verbose {
+            eprintln!("Event '{}'", event.name);
+        }
+
+        // Get entry fees.
+        let (entry_fees, entry_fee_list_cache_key) = self.entry_fees(source, event)?;
+
+        // Get event classes
+        let (class_list, class_list_cache_key) = self.event_request(source, event, EVENT_CLASSES, 
+        &[("includeEntryFees", "true"), ("eventId", &event.id.to_string())])?;
+        let event_classes: Vec<iof::EventClass> = iof::event_classes(&class_list)
+            .map_err(xml_error("event classes", &class_list_cache_key))?;
+        
+        // Get pre-entries
+        let (entry_list, entry_list_cache_key) = self.event_request(source, event, ENTRIES, 
+        &[("includeEntryFees", "true"), 
+                    ("organisationIds", &self.organisation_id.to_string()), 
+                    ("eventIds", &event.id.to_string())])?;
+        let entries: Vec<iof::Entry> = iof::club_entries(&entry_list, self.organisation_id)
+            .map_err(xml_error("entry list", &entry_list_cache_key))?;
+
+        let class_results: Vec<iof::ClassResult> = iof::club_class_results(&result_list, self.organisation_id)
+            .map_err(xml_error("result list", &result_list_cache_key))?;
+
+        let cache_keys: Vec<String> = [event_list_cache_key, &result_list_cache_key, &entry_fee_list_cache_key,
+            &class_list_cache_key, &entry_list_cache_key]
+            .iter()
+            .map(|key| key.to_string())
+            .collect();
+
+        // Gather the races each person took part in. For multi-day events a person appears either
+        // once per stage (one class result per race) or once with a race result per stage, so both are
+        // collected under the same participation to make sure the event is only billed once. The class
+        // may differ between stages.
+        let mut participations: Vec<Participation> = vec![];
+        for class in class_results.iter() {
+            for person_result in class.person_results.iter() {
+                // find reference to person in persons, or create new.
+                let person_index = resolver.person_index(persons, &person_result.competitor);
+
+                let participation = match participations
+                    .iter()
+                    .position(|p| p.person_index == person_index)
+                {
+                    Some(index) => &mut participations[index],
+                    None => {
+                        participations.push(Participation { 
+                            person_index, 
+                            birth_year: birth_year(&person_result.competitor),
+                            races: vec![],
+                            classes: vec![] });
+                        participations.last_mut().unwrap()
+                    },
+                };
+                for race_status in person_result.race_statuses.iter() {
+                    let event_race_id = race_status.event_race_id.unwrap_or(class.event_race_id);
+                    if event.race(&event_race_id).is_none() {
+                        eprintln!("Warning: The result of {} {} at '{}' is for race id {}, which the event does not have, and is not billed.", 
+                            person_result.competitor.given, person_result.competitor.family, event.name, event_race_id);
+                        continue;
+                    }
+                    if !participation.races.iter().any(|(id, _)| *id == event_race_id) {
+                        participation.races.push((event_race_id, race_status.status));
+                        participation.classes.push((event_race_id, class.event_class_id));
+                    }
+                }
+            }
+        }
+
+        for participation in participations.iter_mut() {
+            // Only results for races the event does not have.
+            if participation.races.is_empty() {
+                continue;
+            }
+            participation.races.sort_by_key(|(event_race_id, _)| event.stage_number(event_race_id));
+            // Direct entry fees are those of the class of the first stage.
+            let event_class_id = participation.event_class_id(&participation.races[0].0);
+            let event_class_name = |event_class_id: u64| event_classes
+                .iter()
+                .find(|event_class| event_class.id == event_class_id)
+                .map_or("?".to_string(), |c| c.name.clone());
+            // We are not guaranteed to find the event class, if the entry classes are different from the race
+            // classes (such as for elite events with qualifications).
+            let event_class = event_classes.iter().find(|event_class| event_class.id == event_class_id);
+            let existing_person = &mut persons[participation.person_index];
+
+            let entry = entries.iter().find(|entry| entry.is_for_person(&existing_person.person.id));
+            let source = 
+            // Is this person pre-registered?
+            if let Some(entry) = entry {
+                // Yes.
+                explain::FeeSource::PreEntry(entry)
+            } else if let Some(event_class) = event_class {
+                // No? Ok. Then we get the class id, and the fees from there.
+                explain::FeeSource::DirectEntry(event_class, 
+                    participation.birth_year.unwrap_or(self.current_year(event)), 
+                    participation.birth_year.is_none())
+            } else {
+                return Err(format!("{} {} was not pre-registered at '{}' and the class id {} is unknown.", 
+                    existing_person.person.given, existing_person.person.family, event.name, event_class_id));
             };
-            let byear = match p.person.birth_year {
-                Some(y) => y.to_string(),
-                None => "????".to_string(),
+            let all_fee_ids = match source {
+                explain::FeeSource::PreEntry(entry) => entry.fee_ids.clone(),
+                explain::FeeSource::DirectEntry(event_class, birth_year, _) => 
+                    event_class.direct_entry_fee_ids(&birth_year, &entry_fees),
             };
-            println!("{}\t{}\t{}\t{}", id, p.person.given, p.person.family, byear);
-            p.billable.sort_by_key(|b| b.race_date);
-            for b in p.billable.iter() {
-                if self.include_class_names {
-                    println!("\t{}\t{}\t{}\t{}\t{}\t{}",
-                    b.race_date, b.event_name, b.class_name, b.normal_fee as u64, b.late_fee as u64, if b.dns { "DNS" } else { "" })
+            warn_undefined_fees(&all_fee_ids, &entry_fees, 
+                &format!("{} {}", existing_person.person.given, existing_person.person.family), event);
+
+            let (fee_ids, mut extras) = billing::separate_card_rental(
+                &all_fee_ids, &entry_fees, entry.and_then(|entry| entry.card.as_ref()));
+
+            // Some statuses (e.g. cancelled entries) are not billed at all, depending on club rules.
+            if participation.races.iter().all(|(_, status)| self.waived_statuses.contains(status)) {
+                extras.clear();
+            }
+            let (paid, stage_fees) = billing::split_fees(&fee_ids, &entry_fees, &participation.races, &self.waived_statuses, event.is_multi_day());
+
+            // Every race was checked to be one of the event's when gathered.
+            let race_date = event.first_race_date().unwrap_or(dates::today());
+            let stages: Vec<Stage> = participation.races
+                .iter()
+                .zip(stage_fees.iter())
+                .map(|((event_race_id, status), stage_paid)| Stage {
+                    number: event.stage_number(event_race_id).unwrap_or(0),
+                    name: event.race(event_race_id).and_then(|race| race.name.clone()),
+                    race_date: event.date_for_race(event_race_id).map_or(race_date, |time| time.date),
+                    class_name: event_class_name(participation.event_class_id(event_race_id)),
+                    normal_fee: stage_paid.0,
+                    late_fee: stage_paid.1,
+                    status: *status,
+                })
+                .collect();
+
+            let race_date = stages[0].race_date;
+            let status = BillableEvent::summary_status(&stages);
+            // Single race events have no stage lines, and any fees tied to the race are billed with the event.
+            let stages = if event.is_multi_day() { stages } else { vec![] };
+
+            let provenance = billing::Provenance {
+                event_id: event.id,
+                event_url: event.web_url.clone(),
+                event_race_ids: participation.races.iter().map(|(event_race_id, _)| *event_race_id).collect(),
+                event_class_id,
+                entry_id: entry.and_then(|entry| entry.id),
+                fee_ids: all_fee_ids,
+                cache_keys: cache_keys.clone(),
+            };
+            let billable = BillableEvent { 
+                category: billing::Category::Eventor,
+                race_date, 
+                event_name: event.name.clone(),
+                class_name: event_class_name(event_class_id),
+                normal_fee: paid.0, 
+                late_fee: paid.1, 
+                status,
+                stages,
+                leg: None,
+                extras,
+                provenance,
+                overrides: vec![],
+                paid: false,
+            };
+            if self.explain_person_id.is_some() && self.explain_person_id == existing_person.person.id {
+                let context = explain::Context { 
+                    person: &existing_person.person, event, event_class, 
+                    entry_fees: &entry_fees, waived: &self.waived_statuses };
+                explain::individual(&context, &source, &fee_ids, &participation.races, &billable);
+            }
+            existing_person.billable.push(billable);
+        }
+
+        // Relay teams are billed for the team entry as a whole, and the fee is then divided
+        // between the runners according to the club's rules.
+        for class in class_results.iter().filter(|class| !class.team_results.is_empty()) {
+            let event_class = event_classes.iter().find(|event_class| event_class.id == class.event_class_id);
+            let Some(race_date) = event.date_for_race(&class.event_race_id).map(|time| time.date) else {
+                eprintln!("Warning: The team results in class {} at '{}' are for race id {}, which the event does not have, and are not billed.", 
+                    event_class.map_or(class.event_class_id.to_string(), |c| c.name.clone()), event.name, class.event_race_id);
+                continue
+            };
+            for team in class.team_results.iter() {
+                let runner_ids: Vec<u64> = team.person_results
+                    .iter()
+                    .filter_map(|runner| runner.competitor.id)
+                    .collect();
+
+                let entry = entries.iter().find(|entry| entry.is_for_team(&team.name, &runner_ids));
+                let fee_ids = 
+                if let Some(entry) = entry {
+                    entry.fee_ids.clone()
+                } else if let Some(event_class) = event_class {
+                    // Age dependent team fees are rare. Use the oldest runner, if anyone.
+                    let birth_year = team.person_results
+                        .iter()
+                        .filter_map(|runner| birth_year(&runner.competitor))
+                        .min()
+                        .unwrap_or(self.current_year(event));
+                    event_class.direct_entry_fee_ids(&birth_year, &entry_fees)
                 } else {
-                    println!("\t{}\t{}\t{}\t{}\t{}",
-                    b.race_date, b.event_name, b.normal_fee as u64, b.late_fee as u64, if b.dns { "DNS" } else { "" })
+                    return Err(format!("The team {} was not pre-registered at '{}' and the class id {} is unknown.", 
+                        team.name, event.name, class.event_class_id));
+                };
+                warn_undefined_fees(&fee_ids, &entry_fees, &format!("the team {}", team.name), event);
+                let mut team_fee = iof::EntryFee::paid_fees_from_fee_ids(&fee_ids, &entry_fees);
+                // A replaced fee is for the team, and is divided like the fee from Eventor.
+                let class_name = event_class.map_or("?".to_string(), |c| c.name.clone());
+                let replacement = self.overrides.fee(event.id, &event.name, &class_name);
+                if let Some(replacement) = &replacement {
+                    team_fee = (replacement.normal, replacement.late.unwrap_or(team_fee.1));
+                }
+
+                // Runners from other clubs in a mixed team are billed by their own clubs.
+                let mut runners: Vec<&iof::PersonResult> = team.person_results
+                    .iter()
+                    .filter(|runner| runner.is_from(self.organisation_id))
+                    .collect();
+                runners.sort_by_key(|runner| runner.leg);
+                let statuses: Vec<iof::CompetitorStatus> = runners.iter().map(|runner| runner.status()).collect();
+                let shares = self.team_fee_split.shares(team_fee, team.person_results.len(), &statuses, &self.waived_statuses);
+
+                for ((runner, status), paid) in runners.into_iter().zip(statuses).zip(shares) {
+                    let person_index = resolver.person_index(persons, &runner.competitor);
+                    let billable = BillableEvent { 
+                        category: billing::Category::Eventor,
+                        race_date, 
+                        event_name: event.name.clone(),
+                        class_name: class_name.clone(),
+                        normal_fee: paid.0, 
+                        late_fee: paid.1, 
+                        status,
+                        stages: vec![],
+                        leg: runner.leg,
+                        extras: vec![],
+                        overrides: replacement
+                            .iter()
+                            .filter(|_| !self.waived_statuses.contains(&status))
+                            .map(|replacement| replacement.note.clone())
+                            .collect(),
+                        paid: false,
+                        provenance: billing::Provenance {
+                            event_id: event.id,
+                            event_url: event.web_url.clone(),
+                            event_race_ids: vec![class.event_race_id],
+                            event_class_id: class.event_class_id,
+                            entry_id: entry.and_then(|entry| entry.id),
+                            fee_ids: fee_ids.clone(),
+                            cache_keys: cache_keys.clone(),
+                        },
+                    };
+                    if self.explain_person_id.is_some() && self.explain_person_id == persons[person_index].person.id {
+                        let context = explain::Context { 
+                            person: &persons[person_index].person, event, event_class, 
+                            entry_fees: &entry_fees, waived: &self.waived_statuses };
+                        let share = explain::TeamShare { 
+                            name: &team.name, entry, fee: team_fee, 
+                            team_size: team.person_results.len(), split: self.team_fee_split };
+                        explain::team(&context, &share, &status, &billable);
+                    }
+                    persons[person_index].billable.push(billable);
                 }
             }
         }
+
+        if self.include_services {
+            // Bookings such as accommodation or meals are attached to the member's line for the event. Those
+            // booked by someone without a result get a line of their own, with only the bookings.
+            let (service_request_list, service_request_list_cache_key) = self.event_request(source, event, SERVICE_REQUESTS, 
+            &[("organisationIds", &self.organisation_id.to_string()), 
+                        ("eventId", &event.id.to_string())])?;
+            let service_requests = iof::service_requests(&service_request_list)
+                .map_err(xml_error("service request list", &service_request_list_cache_key))?;
+            for service_request in service_requests.iter() {
+                let extra = Extra {
+                    kind: ExtraKind::Service,
+                    description: if service_request.quantity == 1f64 { 
+                        service_request.name.clone() 
+                    } else { 
+                        format!("{} x {}", service_request.quantity, service_request.name) 
+                    },
+                    amount: service_request.amount,
+                };
+                let has_line = |p: &Person| p.person.id.is_some() && p.person.id == service_request.person_id 
+                    && p.billable.iter().any(|b| b.provenance.event_id == event.id);
+                let person_index = match persons.iter().position(has_line) {
+                    Some(person_index) => person_index,
+                    None => {
+                        let known = |person_id: u64| persons
+                            .iter()
+                            .find(|p| p.person.id == Some(person_id))
+                            .map(|p| p.person.clone())
+                            .or_else(|| roster.and_then(|roster| roster.member(person_id)).cloned());
+                        let competitor = service_request.person.clone().or_else(|| service_request.person_id.and_then(known));
+                        let Some(competitor) = competitor else {
+                            eprintln!("Warning: Service '{}' at '{}' booked by person {}, who has no result at the event and is not known, is not billed.", 
+                                service_request.name, event.name, service_request.person_id.map_or("?".to_string(), |id| id.to_string()));
+                            continue
+                        };
+                        let person_index = resolver.person_index(persons, &competitor);
+                        persons[person_index].billable.push(BillableEvent { 
+                            category: billing::Category::Eventor,
+                            race_date: event.first_race_date().unwrap_or(dates::today()),
+                            event_name: event.name.clone(),
+                            class_name: String::new(),
+                            normal_fee: 0f64, 
+                            late_fee: 0f64, 
+                            status: iof::CompetitorStatus::Inactive,
+                            stages: vec![],
+                            leg: None,
+                            extras: vec![],
+                            overrides: vec![],
+                            paid: false,
+                            provenance: billing::Provenance {
+                                event_id: event.id,
+                                event_url: event.web_url.clone(),
+                                event_race_ids: vec![],
+                                event_class_id: 0,
+                                entry_id: None,
+                                fee_ids: vec![],
+                                cache_keys: vec![event_list_cache_key.to_string()],
+                            },
+                        });
+                        person_index
+                    },
+                };
+                let billable = persons[person_index]
+                    .billable
+                    .iter_mut()
+                    .find(|b| b.provenance.event_id == event.id)
+                    .expect("The person has a line for the event.");
+                if !billable.provenance.cache_keys.contains(&service_request_list_cache_key) {
+                    billable.provenance.cache_keys.push(service_request_list_cache_key.clone());
+                }
+                billable.extras.push(extra);
+            }
+        }
+
+        // Manual corrections come last, so that they see the lines as they would otherwise be billed.
+        let cancelled = event.is_cancelled() && self.cancelled_policy == billing::CancelledPolicy::Waive;
+        for p in persons.iter_mut() {
+            let person_id = p.person.id;
+            for billable in p.billable.iter_mut().filter(|b| b.provenance.event_id == event.id) {
+                if cancelled {
+                    billable.waive();
+                    billable.overrides.push("Event cancelled".to_string());
+                }
+                self.overrides.apply(billable, person_id, &self.waived_statuses, cancelled);
+            }
+        }
+        Ok(())
+    }
+}
+
+// The error for a document that could not be read, telling which response or file it was when known.
+fn xml_error<'a>(what: &'a str, cache_key: &'a str) -> impl Fn(iof::IOFXMLError) -> String + 'a {
+    move |problem| match cache_key {
+        "" => format!("XML parsing error when reading {}: {}", what, problem),
+        cache_key => format!("XML parsing error when reading {} ({}): {}", what, cache_key, problem),
+    }
+}
+
+// Fee ids that the event does not define are left out of the billing, but pointed out.
+fn warn_undefined_fees(fee_ids: &[u64], entry_fees: &[iof::EntryFee], who: &str, event: &iof::Event) {
+    let undefined: Vec<String> = iof::EntryFee::undefined(fee_ids, entry_fees).iter().map(|id| id.to_string()).collect();
+    if !undefined.is_empty() {
+        eprintln!("Warning: The fees for {} at '{}' refer to fee ids that are not defined for the event, and are not billed: {}.", 
+            who, event.name, undefined.join(", "));
     }
 }
 
@@ -284,8 +1069,33 @@ fn main() {
 
     opts.optflag("q", "quiet", "hide additional information while running");
     opts.optflag("n", "class_name", "include class name for each billable event");
+    opts.optflag("r", "services", "include services booked at the event, such as accommodation");
+    opts.optflag("", "roster", "check the persons billed against the club's members in Eventor");
+    opts.optflag("", "all-members", "also list members with nothing to bill, implies --roster");
+    opts.optflag("j", "json", "print the billable events as JSON, including where each fee comes from");
+    opts.optopt("", "format", "output format: tsv, csv, json or xlsx", "csv");
+    opts.optmulti("", "payments", "BgMax file or camt.053 statement with payments to match to the invoices for the period", "payments.xml");
+    opts.optopt("", "invoices", "folder to write an invoice per member, or per household with payers, as HTML and PDF", "invoices/");
+    opts.optflag("", "record", "remember the end of the date range as the last day billed, as --invoices does");
     opts.optflag("s", "starts", "show only the number of starts made");
-    opts.optopt("i", "ignore", "comma-separated list of event IDs to ignore", "34567,35112");
+    opts.optflag("l", "lint", "check the fee structure of the events for likely mistakes");
+    opts.optopt("e", "event", "a single event to process instead of a date range", "42705");
+    opts.optopt("x", "explain", "explain how the fees were computed for a person at the event given with -e", "123456");
+    opts.optopt("m", "overrides", "file with manual corrections, such as events to exclude", "overrides.toml");
+    opts.optopt("w", "waive", "comma-separated list of competitor statuses that are not billed", "DNS,Cancelled");
+    opts.optopt("t", "team_fees", "how relay team fees are billed: equal, club or captain", "equal");
+    opts.optmulti("", "include", "only bill events matching the filter", "discipline=foot");
+    opts.optmulti("", "exclude", "do not bill events matching the filter", "name=O-Ringen*");
+    opts.optopt("", "ledger", "file with members' starts at events outside Eventor", "ledger.toml");
+    opts.optopt("", "aliases", "file with other names and person ids that members appear under", "aliases.toml");
+    opts.optopt("", "payers", "file with who pays for whom, e.g. parents for their children", "payers.toml");
+    opts.optopt("f", "config", "club config file with default settings and event filters", "club.toml");
+    opts.optopt("", "cancelled", "how lines for cancelled events are billed: bill, waive or skip", "waive");
+    opts.optopt("", "export", "write the club's results or entries as IOF XML 2.0.3 instead of billing", "results");
+    opts.optopt("", "export-folder", "folder to write the club's result list for each event to, named by event id", "results/");
+    opts.optflag("u", "update", "fetch the events again, and any data for events changed since it was cached");
+    opts.optopt("", "iof", "IOF XML version to ask Eventor for: 2.0.3 or 3.0", "2.0.3");
+    opts.optopt("d", "data", "folder of IOF XML files to read instead of Eventor, with no API key", "season2022/");
     opts.optopt("c", "cache", "cache folder for requests", "caches/");
     opts.optopt("o", "org_id", "organisation id", "224");
     opts.optflag("h", "help", "show this help menu");
@@ -298,10 +1108,18 @@ fn main() {
             print_usage(opts);
         },
         Ok(extractor) => {
-            if extractor.show_only_starts {
-                println!("Total {} starts", extractor.get_number_of_starts());
+            let done = if extractor.show_only_starts {
+                extractor.get_number_of_starts().map(|starts| println!("Total {} starts", starts))
+            } else if extractor.lint {
+                extractor.lint()
+            } else if let Some(export) = extractor.export {
+                extractor.export(export)
             } else {
-                extractor.run();
+                extractor.run()
+            };
+            if let Err(problem) = done {
+                eprintln!("ERROR: {}", problem);
+                std::process::exit(1);
             }
         }
     };
@@ -0,0 +1,55 @@
+use super::{items, member_label, Output, Report};
+use std::io::{self, Write};
+
+pub struct Csv;
+
+impl Output for Csv {
+    // Quoted as in RFC 4180, with CRLF line endings. Persons with nothing billed get a row without
+    // a line, so that every person is listed.
+    fn write(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        let mut writer = ::csv::WriterBuilder::new()
+            .terminator(::csv::Terminator::CRLF)
+            .flexible(false)
+            .from_writer(out);
+        let payers = !report.households.is_empty();
+
+        let mut header = vec!["Id", "Given", "Family", "Birth year", "Member"];
+        if payers {
+            header.push("Payer");
+        }
+        header.extend(["Category", "Date", "Event", "Class", "Item", "Normal fee", "Late fee", "Status", "Corrections"]);
+        writer.write_record(&header)?;
+
+        for p in report.persons.iter() {
+            let mut person = vec![
+                p.person.id.map_or(String::new(), |id| id.to_string()),
+                p.person.given.clone(),
+                p.person.family.clone(),
+                p.person.birth_year.map_or(String::new(), |year| year.to_string()),
+                member_label(p).to_string(),
+            ];
+            if payers {
+                person.push(report.payer(p).unwrap_or_default().to_string());
+            }
+            if p.billable.is_empty() {
+                writer.write_record(person.iter().cloned().chain((0..9).map(|_| String::new())))?;
+            }
+            for item in p.billable.iter().flat_map(items) {
+                let corrections = if item.item.is_empty() { item.line.overrides.join("; ") } else { String::new() };
+                let line = [
+                    format!("{:?}", item.line.category),
+                    item.date.to_string(),
+                    item.line.display_name(),
+                    item.class_name.to_string(),
+                    item.item,
+                    item.normal_fee.to_string(),
+                    item.late_fee.to_string(),
+                    item.status,
+                    corrections,
+                ];
+                writer.write_record(person.iter().cloned().chain(line))?;
+            }
+        }
+        writer.flush()
+    }
+}
@@ -0,0 +1,30 @@
+use super::{Output, Report};
+use crate::billing::Person;
+use crate::payers::Household;
+use crate::totals::Totals;
+use serde::Serialize;
+use std::io::{self, Write};
+
+pub struct Json;
+
+// The persons, or the households when there are payers, and the totals for the period.
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    persons: Option<&'a [Person]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    households: Option<&'a [Household<'a>]>,
+    totals: Totals,
+}
+
+impl Output for Json {
+    fn write(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        let json = if report.households.is_empty() {
+            JsonReport { persons: Some(report.persons), households: None, totals: report.totals }
+        } else {
+            JsonReport { persons: None, households: Some(&report.households), totals: report.totals }
+        };
+        serde_json::to_writer_pretty(&mut *out, &json)?;
+        writeln!(out)
+    }
+}
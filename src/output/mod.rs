@@ -0,0 +1,133 @@
+use crate::billing::{self, BillableEvent, Person};
+use crate::payers::Household;
+use crate::totals::Totals;
+use chrono::NaiveDate;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+mod csv;
+mod json;
+mod tsv;
+mod xlsx;
+
+// How the billing is written.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    // Tab-separated text with a line per person followed by their lines, for reading.
+    Tsv,
+    // One row per line, stage or extra, with the person repeated on each row.
+    Csv,
+    Json,
+    // A workbook with a summary sheet and a sheet per person.
+    Xlsx,
+}
+
+impl FromStr for Format {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tsv" => Ok(Format::Tsv),
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            "xlsx" => Ok(Format::Xlsx),
+            _ => Err("Unrecognized output format, should be one of tsv, csv, json or xlsx."),
+        }
+    }
+}
+
+impl Format {
+    pub fn is_binary(&self) -> bool {
+        *self == Format::Xlsx
+    }
+}
+
+// Everything billed for the period, sorted as it is to be presented.
+pub struct Report<'a> {
+    pub persons: &'a [Person],
+    // Empty unless the club has payers.
+    pub households: Vec<Household<'a>>,
+    pub totals: Totals,
+    // Whether the club has a share policy, so that the shares are written.
+    pub shares: bool,
+    pub include_class_names: bool,
+}
+
+pub trait Output {
+    fn write(&self, report: &Report, out: &mut dyn Write) -> io::Result<()>;
+}
+
+pub fn output(format: Format) -> Box<dyn Output> {
+    match format {
+        Format::Tsv => Box::new(tsv::Tsv),
+        Format::Csv => Box::new(csv::Csv),
+        Format::Json => Box::new(json::Json),
+        Format::Xlsx => Box::new(xlsx::Xlsx),
+    }
+}
+
+impl Report<'_> {
+    // The payer of each person, when there are payers.
+    fn payer(&self, person: &Person) -> Option<&str> {
+        self.households
+            .iter()
+            .find(|household| household.persons.iter().any(|p| std::ptr::eq(*p, person)))
+            .map(|household| household.payer.as_str())
+    }
+}
+
+// Members are only told from guests when the roster was checked.
+fn member_label(person: &Person) -> &'static str {
+    match person.member {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "",
+    }
+}
+
+// A row in the tabular formats and invoices: a billable line, or one of its extras or stages, which are billed
+// on top of the line.
+pub struct Item<'a> {
+    pub line: &'a BillableEvent,
+    pub date: NaiveDate,
+    // Empty for the line itself.
+    pub item: String,
+    pub class_name: &'a str,
+    pub normal_fee: f64,
+    pub late_fee: f64,
+    pub status: String,
+}
+
+pub fn items(line: &BillableEvent) -> Vec<Item<'_>> {
+    let mut items = vec![Item {
+        line,
+        date: line.race_date,
+        item: String::new(),
+        class_name: &line.class_name,
+        normal_fee: line.normal_fee,
+        late_fee: line.late_fee,
+        status: line.status_label(),
+    }];
+    items.extend(line.extras.iter().map(|extra| Item {
+        line,
+        date: line.race_date,
+        item: format!("{}: {}", extra.kind, extra.description),
+        class_name: &line.class_name,
+        normal_fee: extra.amount,
+        late_fee: 0f64,
+        status: String::new(),
+    }));
+    items.extend(line.stages.iter().map(|stage| Item {
+        line,
+        date: stage.race_date,
+        item: match &stage.name {
+            Some(name) => format!("Stage {}: {}", stage.number, name),
+            None => format!("Stage {}", stage.number),
+        },
+        class_name: &stage.class_name,
+        normal_fee: stage.normal_fee,
+        late_fee: stage.late_fee,
+        status: billing::status_label(&stage.status),
+    }));
+    items
+}
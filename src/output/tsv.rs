@@ -0,0 +1,123 @@
+use super::{Output, Report};
+use crate::billing::{self, Person};
+use crate::totals::Totals;
+use crate::DATE_FORMAT;
+use std::io::{self, Write};
+
+pub struct Tsv;
+
+impl Output for Tsv {
+    fn write(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        if report.households.is_empty() {
+            for p in report.persons.iter() {
+                write_person(report, p, out)?;
+            }
+        }
+        for household in report.households.iter() {
+            writeln!(out, "Payer\t{}", household.payer)?;
+            for p in household.persons.iter() {
+                write_person(report, p, out)?;
+            }
+            // The sums of the normal and late fee columns of the lines above, and then everything to pay.
+            let class_column = if report.include_class_names { "\t" } else { "" };
+            let (normal, late) = column_sums(&household.persons);
+            writeln!(out, "\t\tTotal for {}\t{}{}\t{}\t{}", household.payer, class_column, normal as u64, late as u64, household.totals.total as u64)?;
+        }
+        write_totals(report, out)
+    }
+}
+
+// The sums of the normal and late fee columns as `write_person` prints them: the lines whatever
+// their status, their stages, and their extras in the normal fee column. The totals count DNS
+// fees, card rental and services separately, so they don't match the columns.
+fn column_sums(persons: &[&Person]) -> (f64, f64) {
+    let mut sums = (0f64, 0f64);
+    for b in persons.iter().flat_map(|p| p.billable.iter()) {
+        sums.0 += b.normal_fee + b.extras.iter().map(|extra| extra.amount).sum::<f64>();
+        sums.1 += b.late_fee;
+        for stage in b.stages.iter() {
+            sums.0 += stage.normal_fee;
+            sums.1 += stage.late_fee;
+        }
+    }
+    sums
+}
+
+// The person's line, followed by the billable lines.
+fn write_person(report: &Report, p: &Person, out: &mut dyn Write) -> io::Result<()> {
+    let id: String = match p.person.id {
+        Some(i) => i.to_string(),
+        None => "????".to_string(),
+    };
+    let byear = match p.person.birth_year {
+        Some(y) => y.to_string(),
+        None => "????".to_string(),
+    };
+    // Persons billed who are not in the roster are flagged, as they may be guests.
+    let flag = if p.member == Some(false) { "\tNot a member" } else { "" };
+    writeln!(out, "{}\t{}\t{}\t{}{}", id, p.person.given, p.person.family, byear, flag)?;
+    for b in p.billable.iter() {
+        // Manual corrections are flagged in an extra column, so that they are not mistaken for Eventor data.
+        let flag = if b.overrides.is_empty() { "".to_string() } else { format!("\t{}", b.overrides.join("; ")) };
+        if report.include_class_names {
+            writeln!(out, "\t{}\t{}\t{}\t{}\t{}\t{}{}",
+            b.race_date.format(DATE_FORMAT), b.display_name(), b.class_name, b.normal_fee as u64, b.late_fee as u64, b.status_label(), flag)?
+        } else {
+            writeln!(out, "\t{}\t{}\t{}\t{}\t{}{}",
+            b.race_date.format(DATE_FORMAT), b.display_name(), b.normal_fee as u64, b.late_fee as u64, b.status_label(), flag)?
+        }
+        for extra in b.extras.iter() {
+            // Extras are billed in full, so they go in the normal fee column.
+            let extra_name = format!("  {}: {}", extra.kind, extra.description);
+            if report.include_class_names {
+                writeln!(out, "\t{}\t{}\t\t{}\t0\t", b.race_date.format(DATE_FORMAT), extra_name, extra.amount as u64)?
+            } else {
+                writeln!(out, "\t{}\t{}\t{}\t0\t", b.race_date.format(DATE_FORMAT), extra_name, extra.amount as u64)?
+            }
+        }
+        // Multi-day events get one line per stage below the event line. Fees on the stage lines are
+        // only those the organiser tied to that stage, so the lines can be summed without double billing.
+        for stage in b.stages.iter() {
+            let stage_name = match &stage.name {
+                Some(name) => format!("  Stage {}: {}", stage.number, name),
+                None => format!("  Stage {}", stage.number),
+            };
+            if report.include_class_names {
+                writeln!(out, "\t{}\t{}\t{}\t{}\t{}\t{}",
+                stage.race_date.format(DATE_FORMAT), stage_name, stage.class_name, stage.normal_fee as u64, stage.late_fee as u64, billing::status_label(&stage.status))?
+            } else {
+                writeln!(out, "\t{}\t{}\t{}\t{}\t{}",
+                stage.race_date.format(DATE_FORMAT), stage_name, stage.normal_fee as u64, stage.late_fee as u64, billing::status_label(&stage.status))?
+            }
+        }
+    }
+    Ok(())
+}
+
+// A table with the totals for each person and for the period, after a blank line. The share
+// columns are only there when the club has a share policy, and the paid column when any line has
+// already been paid.
+fn write_totals(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    let paid = report.totals.paid != 0f64;
+    let row = |totals: &Totals| {
+        let mut row = format!("{}\t{}\t{}\t{}\t{}\t{}",
+            totals.normal_fees as u64, totals.late_fees as u64, totals.dns_fees as u64,
+            totals.card_rental as u64, totals.services as u64, totals.total as u64);
+        if report.shares {
+            row += &format!("\t{}\t{}", totals.member_share.unwrap_or(0f64) as u64, totals.club_share.unwrap_or(0f64) as u64);
+        }
+        if paid {
+            row += &format!("\t{}", totals.paid as u64);
+        }
+        row
+    };
+    writeln!(out)?;
+    let share_header = if report.shares { "\tMember share\tClub share" } else { "" };
+    let paid_header = if paid { "\tPaid" } else { "" };
+    writeln!(out, "Id\tGiven\tFamily\tNormal\tLate\tDNS\tCard rental\tServices\tTotal{}{}", share_header, paid_header)?;
+    for p in report.persons.iter() {
+        let id = p.person.id.map_or("????".to_string(), |id| id.to_string());
+        writeln!(out, "{}\t{}\t{}\t{}", id, p.person.given, p.person.family, row(&p.totals))?;
+    }
+    writeln!(out, "\t\tTotal\t{}", row(&report.totals))
+}
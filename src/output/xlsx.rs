@@ -0,0 +1,154 @@
+use super::{items, member_label, Output, Report};
+use crate::billing::Person;
+use crate::totals::Totals;
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+use std::io::{self, Write};
+
+pub struct Xlsx;
+
+impl Output for Xlsx {
+    fn write(&self, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+        let buffer = workbook(report).map_err(|e| io::Error::other(format!("Unable to write the workbook: {}", e)))?;
+        out.write_all(&buffer)
+    }
+}
+
+fn workbook(report: &Report) -> Result<Vec<u8>, XlsxError> {
+    let bold = Format::new().set_bold();
+    let mut workbook = Workbook::new();
+
+    let summary = workbook.add_worksheet().set_name("Summary")?;
+    let payers = !report.households.is_empty();
+    let mut header = vec!["Id", "Given", "Family", "Birth year", "Member"];
+    if payers {
+        header.push("Payer");
+    }
+    let first_total_column = header.len() as u16;
+    header.extend(totals_header(report));
+    summary.write_row_with_format(0, 0, header, &bold)?;
+    let mut row = 1;
+    for p in report.persons.iter() {
+        if let Some(id) = p.person.id {
+            summary.write_number(row, 0, id as f64)?;
+        }
+        summary.write_string(row, 1, &p.person.given)?;
+        summary.write_string(row, 2, &p.person.family)?;
+        if let Some(year) = p.person.birth_year {
+            summary.write_number(row, 3, year as f64)?;
+        }
+        summary.write_string(row, 4, member_label(p))?;
+        if payers {
+            summary.write_string(row, 5, report.payer(p).unwrap_or_default())?;
+        }
+        summary.write_row(row, first_total_column, totals_row(&p.totals, report))?;
+        row += 1;
+    }
+    summary.write_string_with_format(row, 2, "Total", &bold)?;
+    summary.write_row_with_format(row, first_total_column, totals_row(&report.totals, report), &bold)?;
+    summary.set_freeze_panes(1, 0)?;
+    summary.autofit();
+
+    let mut names: Vec<String> = vec![];
+    for p in report.persons.iter() {
+        let name = sheet_name(p, &names);
+        let sheet = workbook.add_worksheet().set_name(&name)?;
+        person_sheet(sheet, report, p, &bold)?;
+        names.push(name);
+    }
+    workbook.save_to_buffer()
+}
+
+// The paid column is only there when any line has already been paid.
+fn totals_header(report: &Report) -> Vec<&'static str> {
+    let mut header = vec!["Normal", "Late", "DNS", "Card rental", "Services", "Total"];
+    if report.shares {
+        header.extend(["Member share", "Club share"]);
+    }
+    if report.totals.paid != 0f64 {
+        header.push("Paid");
+    }
+    header
+}
+
+fn totals_row(totals: &Totals, report: &Report) -> Vec<f64> {
+    let mut row = vec![totals.normal_fees, totals.late_fees, totals.dns_fees, totals.card_rental, totals.services, totals.total];
+    if report.shares {
+        row.extend([totals.member_share.unwrap_or(0f64), totals.club_share.unwrap_or(0f64)]);
+    }
+    if report.totals.paid != 0f64 {
+        row.push(totals.paid);
+    }
+    row
+}
+
+// Sheet names are at most 31 characters, without the characters Excel reserves, and unique.
+fn sheet_name(p: &Person, taken: &[String]) -> String {
+    let name: String = format!("{} {}", p.person.family, p.person.given)
+        .chars()
+        .filter(|c| !"[]:*?/\\".contains(*c))
+        .collect();
+    let mut suffix = String::new();
+    let mut number = 1;
+    loop {
+        let candidate: String = name.chars().take(31 - suffix.chars().count()).collect::<String>().trim().to_string() + &suffix;
+        if !taken.iter().any(|t| t.to_lowercase() == candidate.to_lowercase()) && !candidate.eq_ignore_ascii_case("summary") {
+            return candidate;
+        }
+        number += 1;
+        suffix = format!(" ({})", number);
+    }
+}
+
+// The person's details, their lines with the sums of the fee columns, and their totals.
+fn person_sheet(sheet: &mut Worksheet, report: &Report, p: &Person, bold: &Format) -> Result<(), XlsxError> {
+    let date = Format::new().set_num_format("yyyy-mm-dd");
+    sheet.write_string_with_format(0, 0, format!("{} {}", p.person.given, p.person.family), bold)?;
+    let mut details = vec![
+        ("Id", p.person.id.map_or(String::new(), |id| id.to_string())),
+        ("Birth year", p.person.birth_year.map_or(String::new(), |year| year.to_string())),
+        ("Member", member_label(p).to_string()),
+    ];
+    if let Some(payer) = report.payer(p) {
+        details.push(("Payer", payer.to_string()));
+    }
+    let mut row = 1;
+    for (label, value) in details {
+        sheet.write_string(row, 0, label)?;
+        sheet.write_string(row, 1, value)?;
+        row += 1;
+    }
+
+    row += 1;
+    let header = ["Date", "Event", "Class", "Item", "Normal fee", "Late fee", "Status", "Category", "Corrections"];
+    sheet.write_row_with_format(row, 0, header, bold)?;
+    row += 1;
+    let (mut normal, mut late) = (0f64, 0f64);
+    for item in p.billable.iter().flat_map(items) {
+        sheet.write_datetime_with_format(row, 0, item.date, &date)?;
+        sheet.write_string(row, 1, item.line.display_name())?;
+        sheet.write_string(row, 2, item.class_name)?;
+        sheet.write_string(row, 3, &item.item)?;
+        sheet.write_number(row, 4, item.normal_fee)?;
+        sheet.write_number(row, 5, item.late_fee)?;
+        sheet.write_string(row, 6, &item.status)?;
+        sheet.write_string(row, 7, format!("{:?}", item.line.category))?;
+        if item.item.is_empty() {
+            sheet.write_string(row, 8, item.line.overrides.join("; "))?;
+        }
+        normal += item.normal_fee;
+        late += item.late_fee;
+        row += 1;
+    }
+    sheet.write_string_with_format(row, 1, "Total", bold)?;
+    sheet.write_number_with_format(row, 4, normal, bold)?;
+    sheet.write_number_with_format(row, 5, late, bold)?;
+
+    row += 2;
+    for (label, amount) in totals_header(report).into_iter().zip(totals_row(&p.totals, report)) {
+        sheet.write_string(row, 0, label)?;
+        sheet.write_number(row, 1, amount)?;
+        row += 1;
+    }
+    sheet.autofit();
+    Ok(())
+}
@@ -0,0 +1,277 @@
+use crate::billing::BillableEvent;
+use crate::iof::CompetitorStatus;
+use serde::Deserialize;
+use std::fs;
+
+// The version of the overrides file format that this version of tkassa reads.
+const VERSION: u64 = 1;
+
+// Manual corrections to the billing, read from a TOML file kept by the club. They are applied
+// after the fees have been computed, and every line changed is marked with the reason given.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Overrides {
+    version: u64,
+    #[serde(default)]
+    exclude: Vec<Exclude>,
+    #[serde(default)]
+    fee: Vec<Fee>,
+    #[serde(default)]
+    line: Vec<Line>,
+}
+
+// An event is given by its Eventor id, or for local events and the ledger, which have no Eventor
+// id, by its name.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum EventRef {
+    Id(u64),
+    Name(String),
+}
+
+impl EventRef {
+    fn is_for(&self, event_id: u64, event_name: &str) -> bool {
+        match self {
+            EventRef::Id(id) => *id == event_id,
+            EventRef::Name(name) => event_id == 0 && name == event_name,
+        }
+    }
+}
+
+// An event that is not billed at all, e.g. because it was paid on site.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Exclude {
+    event: EventRef,
+    #[serde(default)]
+    reason: String,
+}
+
+// A fee that is wrong in Eventor. Without a class, it applies to every class at the event.
+// Without a late fee, the computed late fee is kept.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Fee {
+    event: EventRef,
+    class: Option<String>,
+    normal: f64,
+    late: Option<f64>,
+    #[serde(default)]
+    reason: String,
+}
+
+// A correction to one member's line at an event. The amounts are only for adjustments.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Line {
+    event: EventRef,
+    person: u64,
+    action: Action,
+    normal: Option<f64>,
+    late: Option<f64>,
+    #[serde(default)]
+    reason: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Action {
+    // Nothing is billed for the line.
+    Waive,
+    // The fees are set to the amounts given, for the whole entry. A missing amount is left as
+    // computed.
+    Adjust,
+    // The member has already paid, e.g. on site. The fees are kept on the line, but are not due.
+    Paid,
+}
+
+// A fee replacement that applies to a line, with the note to mark the line with.
+pub struct Replacement {
+    pub normal: f64,
+    pub late: Option<f64>,
+    pub note: String,
+}
+
+impl Overrides {
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read overrides file {}: {}", path, e))?;
+        let overrides: Overrides = toml::from_str(&contents)
+            .map_err(|e| format!("Unable to parse overrides file {}: {}", path, e))?;
+        if overrides.version != VERSION {
+            return Err(format!(
+                "Overrides file {} has version {}, but only version {} is supported.",
+                path, overrides.version, VERSION
+            ));
+        }
+        if let Some(line) = overrides.line.iter().find(|l| l.action != Action::Adjust && (l.normal.is_some() || l.late.is_some())) {
+            return Err(format!(
+                "Overrides file {} has amounts for person {} with action {:?}, but amounts are only for \"adjust\".",
+                path, line.person, line.action
+            ));
+        }
+        Ok(overrides)
+    }
+
+    // The reason an event is excluded, for the verbose output.
+    pub fn exclude_reason(&self, event_id: u64, event_name: &str) -> Option<&str> {
+        self.exclude
+            .iter()
+            .find(|exclude| exclude.event.is_for(event_id, event_name))
+            .map(|exclude| exclude.reason.as_str())
+    }
+
+    // The replacement for the fee in a class at an event, if any. The last one given wins.
+    pub fn fee(&self, event_id: u64, event_name: &str, class_name: &str) -> Option<Replacement> {
+        self.fee
+            .iter()
+            .rev()
+            .find(|fee| fee.event.is_for(event_id, event_name) && fee.class.as_ref().is_none_or(|class| class == class_name))
+            .map(|fee| Replacement { normal: fee.normal, late: fee.late, note: note("Fee replaced", &fee.reason) })
+    }
+
+    // Applies the overrides to a person's line. Fee replacements do not apply to lines with a
+    // status that is not billed, nor to lines waived as the event was `cancelled`, but corrections
+    // for the member do. Relay lines are left to the team split, which is given the replaced team
+    // fee.
+    pub fn apply(&self, line: &mut BillableEvent, person_id: Option<u64>, waived: &[CompetitorStatus], cancelled: bool) {
+        let event_id = line.provenance.event_id;
+        let event_name = line.event_name.clone();
+
+        if !waived.contains(&line.status) && !cancelled && line.leg.is_none() {
+            if let Some(fee) = self.fee(event_id, &event_name, &line.class_name) {
+                line.normal_fee = fee.normal;
+                if let Some(late) = fee.late {
+                    line.late_fee = late;
+                }
+                // The replacement is for the entry as a whole.
+                for stage in line.stages.iter_mut() {
+                    stage.normal_fee = 0f64;
+                    stage.late_fee = 0f64;
+                }
+                line.overrides.push(fee.note);
+            }
+        }
+
+        let person_id = match person_id {
+            Some(person_id) => person_id,
+            None => return,
+        };
+        for correction in self.line.iter().filter(|l| l.event.is_for(event_id, &event_name) && l.person == person_id) {
+            match correction.action {
+                Action::Waive => line.waive(),
+                // An adjusted amount is for the entry as a whole, as with fee replacements.
+                Action::Adjust => {
+                    if let Some(normal) = correction.normal {
+                        line.normal_fee = normal;
+                        line.stages.iter_mut().for_each(|stage| stage.normal_fee = 0f64);
+                    }
+                    if let Some(late) = correction.late {
+                        line.late_fee = late;
+                        line.stages.iter_mut().for_each(|stage| stage.late_fee = 0f64);
+                    }
+                }
+                Action::Paid => line.paid = true,
+            }
+            let what = match correction.action {
+                Action::Waive => "Waived",
+                Action::Adjust => "Adjusted",
+                Action::Paid => "Already paid",
+            };
+            line.overrides.push(note(what, &correction.reason));
+        }
+    }
+}
+
+fn note(what: &str, reason: &str) -> String {
+    if reason.is_empty() {
+        what.to_string()
+    } else {
+        format!("{}: {}", what, reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::billing::{Category, Provenance, Stage};
+    use chrono::NaiveDate;
+
+    const OK: CompetitorStatus = CompetitorStatus::OK;
+
+    fn stage(number: usize) -> Stage {
+        Stage {
+            number,
+            name: None,
+            race_date: NaiveDate::from_ymd_opt(2022, 7, number as u32).unwrap(),
+            class_name: "H21".to_string(),
+            normal_fee: 100f64,
+            late_fee: 50f64,
+            status: OK,
+        }
+    }
+
+    // A multi-day entry with an entry fee and fees for each of its two stages.
+    fn line() -> BillableEvent {
+        BillableEvent {
+            category: Category::Eventor,
+            race_date: NaiveDate::from_ymd_opt(2022, 7, 1).unwrap(),
+            event_name: "Tredagars".to_string(),
+            class_name: "H21".to_string(),
+            normal_fee: 200f64,
+            late_fee: 0f64,
+            status: OK,
+            stages: vec![stage(1), stage(2)],
+            leg: None,
+            extras: vec![],
+            provenance: Provenance {
+                event_id: 100,
+                event_url: None,
+                event_race_ids: vec![1, 2],
+                event_class_id: 0,
+                entry_id: None,
+                fee_ids: vec![],
+                cache_keys: vec![],
+            },
+            overrides: vec![],
+            paid: false,
+        }
+    }
+
+    fn overrides(text: &str) -> Overrides {
+        toml::from_str(&format!("version = 1\n{}", text)).unwrap()
+    }
+
+    fn fees(line: &BillableEvent) -> (f64, f64) {
+        let stages = line.stages.iter().fold((0f64, 0f64), |(normal, late), s| (normal + s.normal_fee, late + s.late_fee));
+        (line.normal_fee + stages.0, line.late_fee + stages.1)
+    }
+
+    #[test]
+    fn test_adjust_multi_day_entry() {
+        let overrides = overrides("[[line]]\nevent = 100\nperson = 7\naction = \"adjust\"\nnormal = 250");
+        let mut line = line();
+        overrides.apply(&mut line, Some(7), &[], false);
+        // The stage fees are not billed on top of the adjusted fee, but the late fees are kept.
+        assert_eq!(fees(&line), (250f64, 100f64));
+        assert_eq!(line.overrides, vec!["Adjusted".to_string()]);
+    }
+
+    #[test]
+    fn test_replace_fee() {
+        let overrides = overrides("[[fee]]\nevent = 100\nclass = \"H21\"\nnormal = 300\nlate = 0");
+        let mut line = line();
+        overrides.apply(&mut line, Some(7), &[], false);
+        assert_eq!(fees(&line), (300f64, 0f64));
+    }
+
+    #[test]
+    fn test_no_fee_replacement_for_cancelled_event() {
+        let overrides = overrides("[[fee]]\nevent = 100\nnormal = 300");
+        let mut line = line();
+        line.waive();
+        overrides.apply(&mut line, Some(7), &[], true);
+        assert_eq!(fees(&line), (0f64, 0f64));
+        assert!(line.overrides.is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+use crate::billing::Person;
+use crate::roster::Roster;
+use crate::totals::Totals;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+// The version of the payers file format that this version of tkassa reads.
+const VERSION: u64 = 2;
+
+// Who pays for whom, e.g. parents for their children, kept by the club in a TOML file. Persons not
+// listed pay for themselves.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Payers {
+    version: u64,
+    #[serde(default)]
+    payer: Vec<Payer>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Payer {
+    // Tells households apart, also when two payers have the same name.
+    id: u64,
+    name: String,
+    email: Option<String>,
+    // Person ids in Eventor.
+    persons: Vec<u64>,
+}
+
+// The persons billed to one payer. The persons are kept as they are, so that statistics per person
+// are the same as without payers.
+#[derive(Debug, Serialize)]
+pub struct Household<'a> {
+    pub payer: String,
+    // The id of the payer in the payers file, or none for a person paying for themself.
+    pub payer_id: Option<u64>,
+    pub email: Option<String>,
+    pub persons: Vec<&'a Person>,
+    pub totals: Totals,
+}
+
+impl Payers {
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read payers file {}: {}", path, e))?;
+        Payers::parse(&contents, path)
+    }
+
+    fn parse(contents: &str, path: &str) -> Result<Self, String> {
+        let payers: Payers = toml::from_str(contents)
+            .map_err(|e| format!("Unable to parse payers file {}: {}", path, e))?;
+        if payers.version != VERSION {
+            return Err(format!(
+                "Payers file {} has version {}, but only version {} is supported.",
+                path, payers.version, VERSION
+            ));
+        }
+        for (index, payer) in payers.payer.iter().enumerate() {
+            // The name is what the household is shown by, so two payers with the same name could
+            // not be told apart in the output.
+            if let Some(other) = payers.payer[..index]
+                .iter()
+                .find(|other| other.id == payer.id || normalised(&other.name) == normalised(&payer.name))
+            {
+                return Err(format!(
+                    "Payers '{}' (id {}) and '{}' (id {}) have the same id or name in payers file {}.",
+                    other.name, other.id, payer.name, payer.id, path
+                ));
+            }
+            for person in payer.persons.iter() {
+                if let Some(other) = payers.payer[..index].iter().find(|other| other.persons.contains(person)) {
+                    return Err(format!(
+                        "Person {} is paid for by both '{}' and '{}' in payers file {}.",
+                        person, other.name, payer.name, path
+                    ));
+                }
+            }
+        }
+        Ok(payers)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.payer.is_empty()
+    }
+
+    // Persons listed who are not members, likely a mistyped person id.
+    pub fn validate(&self, roster: &Roster) -> Vec<String> {
+        self.payer
+            .iter()
+            .flat_map(|payer| payer.persons.iter().map(move |person| (payer, person)))
+            .filter(|(_, person)| !roster.is_member(**person))
+            .map(|(payer, person)| format!("Person {}, paid for by '{}', is not a member of the club.", person, payer.name))
+            .collect()
+    }
+
+    // The persons grouped by payer, in the order of the first person of each household.
+    pub fn households<'a>(&self, persons: &'a [Person]) -> Vec<Household<'a>> {
+        let mut households: Vec<Household<'a>> = vec![];
+        for person in persons.iter() {
+            let payer = self
+                .payer
+                .iter()
+                .find(|payer| person.person.id.is_some_and(|id| payer.persons.contains(&id)));
+            let payer_id = payer.map(|payer| payer.id);
+            let (name, email) = match payer {
+                Some(payer) => (payer.name.clone(), payer.email.clone()),
+                None => (format!("{} {}", person.person.given, person.person.family), None),
+            };
+            let household = match households.iter_mut().position(|h| payer_id.is_some() && h.payer_id == payer_id) {
+                Some(index) => &mut households[index],
+                None => {
+                    households.push(Household { payer: name, payer_id, email, persons: vec![], totals: Totals::default() });
+                    households.last_mut().unwrap()
+                }
+            };
+            household.persons.push(person);
+            household.totals += person.totals;
+        }
+        households
+    }
+}
+
+// A name in lower case and with single spaces, as names are compared.
+fn normalised(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<&str>>().join(" ").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iof::Competitor;
+
+    fn person(id: u64) -> Person {
+        Person {
+            person: Competitor { id: Some(id), given: "Anna".to_string(), family: "Berg".to_string(), birth_year: None },
+            billable: vec![],
+            member: None,
+            totals: Totals::default(),
+        }
+    }
+
+    #[test]
+    fn test_households_by_payer_id() {
+        let payers = Payers::parse(
+            "version = 2\n\
+             [[payer]]\nid = 1\nname = \"Familjen Berg\"\npersons = [1, 3]\n\
+             [[payer]]\nid = 2\nname = \"Familjen Berg i Ytterby\"\npersons = [2]\n",
+            "payers.toml",
+        )
+        .unwrap();
+        let persons = vec![person(1), person(2), person(3), person(4)];
+        let households = payers.households(&persons);
+        let ids: Vec<(Option<u64>, usize)> = households.iter().map(|h| (h.payer_id, h.persons.len())).collect();
+        assert_eq!(ids, vec![(Some(1), 2), (Some(2), 1), (None, 1)]);
+    }
+
+    #[test]
+    fn test_same_name_is_an_error() {
+        let result = Payers::parse(
+            "version = 2\n\
+             [[payer]]\nid = 1\nname = \"Familjen Berg\"\npersons = [1]\n\
+             [[payer]]\nid = 2\nname = \"familjen  berg\"\npersons = [2]\n",
+            "payers.toml",
+        );
+        assert!(result.unwrap_err().contains("same id or name"));
+    }
+
+    #[test]
+    fn test_same_id_is_an_error() {
+        let result = Payers::parse(
+            "version = 2\n\
+             [[payer]]\nid = 1\nname = \"Familjen Berg\"\npersons = [1]\n\
+             [[payer]]\nid = 1\nname = \"Familjen Lind\"\npersons = [2]\n",
+            "payers.toml",
+        );
+        assert!(result.unwrap_err().contains("same id or name"));
+    }
+
+    #[test]
+    fn test_payer_id_is_required() {
+        let result = Payers::parse("version = 2\n[[payer]]\nname = \"Familjen Berg\"\npersons = [1]\n", "payers.toml");
+        assert!(result.is_err());
+    }
+}
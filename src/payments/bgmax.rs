@@ -0,0 +1,156 @@
+use super::Payment;
+use chrono::NaiveDate;
+
+// A field of a BgMax record, by the 1-based positions in Bankgirot's specification.
+fn field(record: &[char], from: usize, to: usize) -> String {
+    record.iter().skip(from - 1).take(to + 1 - from).collect::<String>().trim().to_string()
+}
+
+// Amounts are given in öre.
+fn amount(record: &[char], from: usize, to: usize) -> Option<f64> {
+    field(record, from, to).parse::<u64>().ok().map(|ore| ore as f64 / 100f64)
+}
+
+// Reads a BgMax file from Bankgirot, in ISO 8859-1. Payments (record 20) and deductions (record
+// 21) are followed by their name (26) and information (25) records, and each deposit (15) gives
+// the date of the payments since the last one. A payment for several references gives the others
+// in extra reference records, 22 for a positive and 23 for a negative amount, and its own amount
+// is the total. Each extra reference becomes a payment of its own, and its amount is taken from
+// the total, so that every reference is matched with its part.
+pub fn parse(bytes: &[u8], path: &str) -> Result<Vec<Payment>, String> {
+    let text: String = bytes.iter().map(|b| *b as char).collect();
+    let mut payments: Vec<Payment> = vec![];
+    // Payments not yet given a date by a deposit record.
+    let mut undated = 0;
+    // Where the parts of the last payment start.
+    let mut first_part = 0;
+    for (index, line) in text.lines().enumerate() {
+        let record: Vec<char> = line.trim_end_matches('\r').chars().collect();
+        let problem = |what: &str| format!("BgMax file {}, line {}: {}", path, index + 1, what);
+        if index == 0 && !line.starts_with("01BGMAX") {
+            return Err(problem("not a BgMax file, which starts with 01BGMAX."));
+        }
+        match field(&record, 1, 2).as_str() {
+            code @ ("20" | "21" | "22" | "23") => {
+                let amount = amount(&record, 38, 55).ok_or_else(|| problem("invalid amount."))?;
+                let amount = if code == "21" || code == "23" { -amount } else { amount };
+                let reference = field(&record, 13, 37);
+                if code == "22" || code == "23" {
+                    let payment = payments
+                        .get_mut(first_part)
+                        .filter(|_| undated > 0)
+                        .ok_or_else(|| problem("an extra reference without a payment before it."))?;
+                    payment.amount -= amount;
+                } else {
+                    first_part = payments.len();
+                }
+                payments.push(Payment {
+                    date: None,
+                    amount,
+                    reference: Some(reference).filter(|reference| !reference.is_empty()),
+                    message: None,
+                    name: None,
+                    source: format!("{}:{}", path, index + 1),
+                });
+                undated += 1;
+            }
+            // Information and the payer's name, for every part of the payment.
+            code @ ("25" | "26") => {
+                for payment in payments[first_part..].iter_mut() {
+                    if code == "26" {
+                        payment.name = Some(field(&record, 3, 37));
+                    } else {
+                        let text = field(&record, 3, 52);
+                        payment.message = Some(match &payment.message {
+                            Some(message) => format!("{} {}", message, text),
+                            None => text,
+                        });
+                    }
+                }
+            }
+            "15" => {
+                let date = NaiveDate::parse_from_str(&field(&record, 38, 45), "%Y%m%d").map_err(|_| problem("invalid payment date."))?;
+                let count = payments.len();
+                for payment in payments[count - undated..].iter_mut() {
+                    payment.date = Some(date);
+                }
+                undated = 0;
+                first_part = count;
+            }
+            _ => {}
+        }
+    }
+    Ok(payments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A payment record with the sender's bankgiro number, a reference and an amount in öre.
+    fn payment(code: &str, reference: &str, ore: u64) -> String {
+        format!("{}{:<10}{:<25}{:018}", code, "9912346", reference, ore)
+    }
+
+    fn deposit(date: &str) -> String {
+        format!("15{:<35}{}", "", date)
+    }
+
+    fn parse_lines(lines: &[String]) -> Result<Vec<Payment>, String> {
+        let text = ["01BGMAX".to_string()].iter().chain(lines).cloned().collect::<Vec<String>>().join("\r\n");
+        // In ISO 8859-1, as from Bankgirot.
+        let bytes: Vec<u8> = text.chars().map(|c| c as u8).collect();
+        parse(&bytes, "bgmax.txt")
+    }
+
+    #[test]
+    fn test_parse() {
+        let payments = parse_lines(&[
+            "05".to_string(),
+            payment("20", "2201011175", 97000),
+            "26Anna Andersson".to_string(),
+            "25Tävlingsavgifter".to_string(),
+            payment("21", "", 5000),
+            deposit("20220315"),
+            "70".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(payments.len(), 2);
+        assert_eq!(payments[0].amount, 970f64);
+        assert_eq!(payments[0].reference.as_deref(), Some("2201011175"));
+        assert_eq!(payments[0].name.as_deref(), Some("Anna Andersson"));
+        assert_eq!(payments[0].message.as_deref(), Some("Tävlingsavgifter"));
+        assert_eq!(payments[0].date, NaiveDate::from_ymd_opt(2022, 3, 15));
+        assert_eq!(payments[0].source, "bgmax.txt:3");
+        assert_eq!(payments[1].amount, -50f64);
+        assert_eq!(payments[1].reference, None);
+        assert_eq!(payments[1].date, NaiveDate::from_ymd_opt(2022, 3, 15));
+    }
+
+    #[test]
+    fn test_parse_extra_references() {
+        // 1200 in total: 970 for one reference, 300 for another and 70 less for a third.
+        let payments = parse_lines(&[
+            payment("20", "2201011175", 120000),
+            payment("22", "2201011258", 30000),
+            payment("23", "2201011340", 7000),
+            "26Familjen Berg".to_string(),
+            deposit("20220316"),
+        ])
+        .unwrap();
+        let amounts: Vec<f64> = payments.iter().map(|p| p.amount).collect();
+        assert_eq!(amounts, vec![970f64, 300f64, -70f64]);
+        assert_eq!(payments[1].reference.as_deref(), Some("2201011258"));
+        assert!(payments.iter().all(|p| p.name.as_deref() == Some("Familjen Berg")));
+        assert!(payments.iter().all(|p| p.date == NaiveDate::from_ymd_opt(2022, 3, 16)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(b"01XML", "bgmax.txt").is_err());
+        assert!(parse_lines(&[payment("22", "2201011258", 30000)]).is_err());
+        assert!(parse_lines(&[payment("20", "2201011175", 100), deposit("20220316"), payment("23", "1", 100)]).is_err());
+        assert!(parse_lines(&["20".to_string() + &" ".repeat(35) + "12x"]).is_err());
+        assert!(parse_lines(&[payment("20", "1", 100), deposit("2022-03-16")]).is_err());
+    }
+}
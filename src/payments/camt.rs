@@ -0,0 +1,77 @@
+use super::Payment;
+use chrono::NaiveDate;
+use xmltree::Element;
+
+fn text(element: &Element, path: &[&str]) -> Option<String> {
+    let mut element = element;
+    for name in path {
+        element = element.get_child(*name)?;
+    }
+    element.get_text().map(|text| text.trim().to_string())
+}
+
+fn children<'a>(element: &'a Element, name: &'a str) -> impl Iterator<Item = &'a Element> {
+    element.children.iter().filter_map(|node| node.as_element()).filter(move |child| child.name == name)
+}
+
+fn date(entry: &Element) -> Option<NaiveDate> {
+    ["BookgDt", "ValDt"]
+        .iter()
+        .find_map(|kind| text(entry, &[kind, "Dt"]).or_else(|| text(entry, &[kind, "DtTm"])))
+        .and_then(|date| NaiveDate::parse_from_str(date.get(..10)?, "%Y-%m-%d").ok())
+}
+
+// Reads an ISO 20022 bank statement (camt.053). Each credit entry is a payment, or one payment per
+// transaction for a batch. Reversals are payments with a negative amount.
+pub fn parse(root: &Element, path: &str) -> Result<Vec<Payment>, String> {
+    let statements = root
+        .get_child("BkToCstmrStmt")
+        .ok_or_else(|| format!("{} is not a camt.053 bank statement.", path))?;
+    let mut payments = vec![];
+    for (statement_index, statement) in children(statements, "Stmt").enumerate() {
+        for (entry_index, entry) in children(statement, "Ntry").enumerate() {
+            if text(entry, &["CdtDbtInd"]).as_deref() != Some("CRDT") {
+                continue;
+            }
+            let sign = if text(entry, &["RvslInd"]).as_deref() == Some("true") { -1f64 } else { 1f64 };
+            let source = format!("{}: statement {}, entry {}", path, statement_index + 1, entry_index + 1);
+            let entry_amount = text(entry, &["Amt"])
+                .and_then(|amount| amount.parse::<f64>().ok())
+                .ok_or_else(|| format!("{}: invalid amount.", source))?;
+            let transactions: Vec<&Element> = children(entry, "NtryDtls").flat_map(|details| children(details, "TxDtls")).collect();
+            if transactions.is_empty() {
+                payments.push(Payment {
+                    date: date(entry),
+                    amount: sign * entry_amount,
+                    reference: None,
+                    message: text(entry, &["AddtlNtryInf"]),
+                    name: None,
+                    source,
+                });
+                continue;
+            }
+            let single = transactions.len() == 1;
+            for (transaction_index, transaction) in transactions.into_iter().enumerate() {
+                let amount = text(transaction, &["Amt"])
+                    .or_else(|| text(transaction, &["AmtDtls", "TxAmt", "Amt"]))
+                    .and_then(|amount| amount.parse::<f64>().ok())
+                    .or(if single { Some(entry_amount) } else { None })
+                    .ok_or_else(|| format!("{}, transaction {}: invalid amount.", source, transaction_index + 1))?;
+                let remittance = transaction.get_child("RmtInf");
+                let message: Vec<String> = remittance
+                    .map(|remittance| children(remittance, "Ustrd").filter_map(|u| u.get_text().map(|t| t.trim().to_string())).collect())
+                    .unwrap_or_default();
+                payments.push(Payment {
+                    date: date(entry),
+                    amount: sign * amount,
+                    reference: remittance.and_then(|remittance| text(remittance, &["Strd", "CdtrRefInf", "Ref"])),
+                    message: Some(message.join(" ")).filter(|message| !message.is_empty()),
+                    name: text(transaction, &["RltdPties", "Dbtr", "Nm"])
+                        .or_else(|| text(transaction, &["RltdPties", "Dbtr", "Pty", "Nm"])),
+                    source: if single { source.clone() } else { format!("{}, transaction {}", source, transaction_index + 1) },
+                });
+            }
+        }
+    }
+    Ok(payments)
+}
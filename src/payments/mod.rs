@@ -0,0 +1,275 @@
+use crate::identity::name_parts;
+use crate::invoice::{ocr, Invoice};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use xmltree::Element;
+
+mod bgmax;
+mod camt;
+
+// Amounts closer than this are the same, as banks give them in hundredths.
+const TOLERANCE: f64 = 0.005;
+
+// An incoming payment from a bank file.
+#[derive(Debug, Serialize)]
+pub struct Payment {
+    pub date: Option<NaiveDate>,
+    pub amount: f64,
+    // The structured reference, normally an OCR number.
+    pub reference: Option<String>,
+    // Free text from the payer.
+    pub message: Option<String>,
+    pub name: Option<String>,
+    // The file and the record or entry the payment is from.
+    pub source: String,
+}
+
+// Reads the payments in a BgMax file or a camt.053 statement, told apart by their contents.
+pub fn from_file(path: &str) -> Result<Vec<Payment>, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Unable to read payments file {}: {}", path, e))?;
+    if bytes.starts_with(b"01BGMAX") {
+        return bgmax::parse(&bytes, path);
+    }
+    match Element::parse(bytes.as_slice()) {
+        Ok(root) => camt::parse(&root, path),
+        Err(_) => Err(format!("Payments file {} is neither a BgMax file nor a camt.053 statement.", path)),
+    }
+}
+
+// How a payment was matched to an invoice, from most to least certain.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum MatchedBy {
+    Reference,
+    // The reference was found in the payer's message, such as for Swish payments.
+    Message,
+    // The payer's name, and the amount if several invoices have the name.
+    Name,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum PaymentStatus {
+    Paid,
+    PartiallyPaid,
+    Overpaid,
+    Outstanding,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MatchedPayment<'a> {
+    #[serde(flatten)]
+    pub payment: &'a Payment,
+    pub matched_by: MatchedBy,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InvoicePayments<'a> {
+    pub reference: String,
+    pub recipient: String,
+    pub amount: f64,
+    pub paid: f64,
+    pub status: PaymentStatus,
+    pub payments: Vec<MatchedPayment<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaymentReport<'a> {
+    pub invoices: Vec<InvoicePayments<'a>>,
+    pub unmatched: Vec<&'a Payment>,
+}
+
+fn digits(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+// The names on the invoice: the payer's and those of the persons paid for.
+fn names(invoice: &Invoice) -> Vec<Vec<String>> {
+    let mut names = vec![name_parts(&invoice.recipient)];
+    names.extend(invoice.persons.iter().map(|p| name_parts(&format!("{} {}", p.person.given, p.person.family))));
+    names
+}
+
+// The invoice a payment is for, if it can be told.
+fn find(invoices: &[Invoice], payment: &Payment) -> Option<(usize, MatchedBy)> {
+    if let Some(reference) = payment.reference.as_deref().map(digits) {
+        if let Some(index) = invoices.iter().position(|invoice| invoice.reference == reference) {
+            return Some((index, MatchedBy::Reference));
+        }
+    }
+
+    // Payers writing the reference by hand may add other text, or spaces within it.
+    let text = digits(&[payment.reference.clone(), payment.message.clone()].into_iter().flatten().collect::<Vec<String>>().join(" "));
+    let numbers: Vec<&str> = text.split(|c: char| !c.is_ascii_digit()).filter(|n| ocr::is_valid(n)).collect();
+    let found: Vec<usize> = (0..invoices.len()).filter(|index| numbers.contains(&invoices[*index].reference.as_str())).collect();
+    if found.len() == 1 {
+        return Some((found[0], MatchedBy::Message));
+    }
+
+    let payer = name_parts(payment.name.as_deref()?);
+    let found: Vec<usize> = (0..invoices.len())
+        .filter(|index| names(&invoices[*index]).iter().any(|name| !name.is_empty() && name.iter().all(|part| payer.contains(part))))
+        .collect();
+    match found.len() {
+        0 => None,
+        1 => Some((found[0], MatchedBy::Name)),
+        _ => {
+            let same_amount: Vec<usize> = found.into_iter().filter(|index| (invoices[*index].amount - payment.amount).abs() < TOLERANCE).collect();
+            if same_amount.len() == 1 { Some((same_amount[0], MatchedBy::Name)) } else { None }
+        }
+    }
+}
+
+// Matches the payments to the invoices of the billing period.
+pub fn reconcile<'a>(invoices: &[Invoice], payments: &'a [Payment]) -> PaymentReport<'a> {
+    let mut report = PaymentReport {
+        invoices: invoices
+            .iter()
+            .map(|invoice| InvoicePayments {
+                reference: invoice.reference.clone(),
+                recipient: invoice.recipient.clone(),
+                amount: invoice.amount,
+                paid: 0f64,
+                status: PaymentStatus::Outstanding,
+                payments: vec![],
+            })
+            .collect(),
+        unmatched: vec![],
+    };
+    for payment in payments.iter() {
+        match find(invoices, payment) {
+            Some((index, matched_by)) => {
+                report.invoices[index].paid += payment.amount;
+                report.invoices[index].payments.push(MatchedPayment { payment, matched_by });
+            }
+            None => report.unmatched.push(payment),
+        }
+    }
+    for invoice in report.invoices.iter_mut() {
+        invoice.status = if invoice.paid.abs() < TOLERANCE {
+            PaymentStatus::Outstanding
+        } else if (invoice.paid - invoice.amount).abs() < TOLERANCE {
+            PaymentStatus::Paid
+        } else if invoice.paid < invoice.amount {
+            PaymentStatus::PartiallyPaid
+        } else {
+            PaymentStatus::Overpaid
+        };
+    }
+    report
+}
+
+fn describe(payment: &Payment) -> String {
+    format!(
+        "\t{}\t{:.2}\t{}\t{}\t{}",
+        payment.date.map_or(String::new(), |date| date.to_string()),
+        payment.amount,
+        payment.name.as_deref().unwrap_or(""),
+        [payment.reference.as_deref(), payment.message.as_deref()].into_iter().flatten().collect::<Vec<&str>>().join(" "),
+        payment.source
+    )
+}
+
+impl PaymentReport<'_> {
+    // A line per invoice followed by its payments, the payments not matched to any invoice, and
+    // the totals, as tab-separated text.
+    pub fn write_text(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "Reference\tPayer\tAmount\tPaid\tStatus")?;
+        for invoice in self.invoices.iter() {
+            writeln!(out, "{}\t{}\t{:.2}\t{:.2}\t{:?}", invoice.reference, invoice.recipient, invoice.amount, invoice.paid, invoice.status)?;
+            for matched in invoice.payments.iter() {
+                writeln!(out, "{}\tMatched by {:?}", describe(matched.payment), matched.matched_by)?;
+            }
+        }
+        if !self.unmatched.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "Unmatched payments")?;
+            for payment in self.unmatched.iter() {
+                writeln!(out, "{}", describe(payment))?;
+            }
+        }
+        let invoiced: f64 = self.invoices.iter().map(|invoice| invoice.amount).sum();
+        let paid: f64 = self.invoices.iter().map(|invoice| invoice.paid).sum();
+        let outstanding: f64 = self.invoices.iter().map(|invoice| (invoice.amount - invoice.paid).max(0f64)).sum();
+        writeln!(out)?;
+        writeln!(out, "Invoiced\t{:.2}", invoiced)?;
+        writeln!(out, "Paid\t{:.2}", paid)?;
+        writeln!(out, "Outstanding\t{:.2}", outstanding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::DateRange;
+    use crate::totals::Totals;
+
+    // A valid OCR reference from the number.
+    fn reference(number: &str) -> String {
+        format!("{}{}", number, ocr::check_digit(number))
+    }
+
+    fn invoice(recipient: &str, number: &str, amount: f64) -> Invoice<'static> {
+        let date = NaiveDate::from_ymd_opt(2022, 3, 1).unwrap();
+        Invoice {
+            recipient: recipient.to_string(),
+            email: None,
+            persons: vec![],
+            totals: Totals::default(),
+            amount,
+            reference: reference(number),
+            period: DateRange { from: NaiveDate::from_ymd_opt(2022, 1, 1).unwrap(), to: date },
+            date,
+            due_date: date,
+            swish: None,
+        }
+    }
+
+    fn payment(amount: f64, reference: Option<&str>, message: Option<&str>, name: Option<&str>) -> Payment {
+        Payment {
+            date: None,
+            amount,
+            reference: reference.map(str::to_string),
+            message: message.map(str::to_string),
+            name: name.map(str::to_string),
+            source: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let invoices = vec![
+            invoice("Anna Andersson", "220101117", 970f64),
+            invoice("Bo Berg", "220101125", 1020f64),
+            invoice("Cecilia Carlsson", "220101134", 500f64),
+            invoice("Dan Dahl", "220101142", 200f64),
+        ];
+        let payments = vec![
+            payment(970f64, Some(&reference("220101117")), None, None),
+            payment(500f64, None, Some(&format!("Avgift 220101 {}", &reference("220101125")[6..])), None),
+            payment(300f64, None, None, Some("CARLSSON, CECILIA")),
+            payment(300f64, None, None, Some("Carlsson, Cecilia")),
+            payment(100f64, Some("123"), None, Some("Erik Ek")),
+        ];
+        let report = reconcile(&invoices, &payments);
+        let statuses: Vec<PaymentStatus> = report.invoices.iter().map(|i| i.status).collect();
+        assert_eq!(statuses, vec![PaymentStatus::Paid, PaymentStatus::PartiallyPaid, PaymentStatus::Overpaid, PaymentStatus::Outstanding]);
+        assert!(matches!(report.invoices[0].payments[0].matched_by, MatchedBy::Reference));
+        assert!(matches!(report.invoices[1].payments[0].matched_by, MatchedBy::Message));
+        assert!(matches!(report.invoices[2].payments[0].matched_by, MatchedBy::Name));
+        assert_eq!(report.invoices[2].paid, 600f64);
+        assert_eq!(report.unmatched.len(), 1);
+        assert_eq!(report.unmatched[0].name.as_deref(), Some("Erik Ek"));
+    }
+
+    #[test]
+    fn test_reconcile_by_name_and_amount() {
+        // Two invoices with the payer's name, told apart by the amount.
+        let invoices = vec![invoice("Familjen Berg", "220101117", 970f64), invoice("Familjen Berg", "220101125", 300f64)];
+        let payments = vec![payment(300f64, None, None, Some("Berg familjen")), payment(400f64, None, None, Some("Familjen Berg"))];
+        let report = reconcile(&invoices, &payments);
+        assert_eq!(report.invoices[1].paid, 300f64);
+        assert_eq!(report.invoices[0].paid, 0f64);
+        assert_eq!(report.unmatched.len(), 1);
+    }
+}
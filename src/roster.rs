@@ -0,0 +1,67 @@
+use crate::billing::Person;
+use crate::identity::{self, Resolution};
+use crate::iof::Competitor;
+use crate::totals::Totals;
+
+// The club's members as listed in Eventor. Persons are otherwise only found through results, so
+// the roster is what tells members from guests running for the club, and its birth dates are
+// trusted over those in result lists.
+pub struct Roster {
+    members: Vec<Competitor>,
+}
+
+impl Roster {
+    pub fn new(members: Vec<Competitor>) -> Roster {
+        Roster { members }
+    }
+
+    // The member, by person id if known, or else by name and birth date if only one member matches.
+    pub fn find(&self, competitor: &Competitor) -> Option<&Competitor> {
+        if competitor.id.is_some() {
+            return self.members.iter().find(|member| member.id == competitor.id);
+        }
+        match identity::resolve(self.members.iter(), competitor) {
+            Resolution::Unique(index, _) => Some(&self.members[index]),
+            Resolution::Ambiguous(_) | Resolution::NotFound => None,
+        }
+    }
+
+    pub fn member(&self, person_id: u64) -> Option<&Competitor> {
+        self.members.iter().find(|member| member.id == Some(person_id))
+    }
+
+    pub fn is_member(&self, person_id: u64) -> bool {
+        self.members.iter().any(|member| member.id == Some(person_id))
+    }
+
+    pub fn birth_year(&self, competitor: &Competitor) -> Option<u64> {
+        self.find(competitor)
+            .and_then(|member| member.birth_year)
+            .or(competitor.birth_year)
+    }
+
+    // Marks each person as a member or not, with the id, name and birth date of members taken from
+    // the roster. With `all_members`, members with nothing to bill are added too.
+    pub fn apply(&self, persons: &mut Vec<Person>, all_members: bool) {
+        for person in persons.iter_mut() {
+            match self.find(&person.person) {
+                Some(member) => {
+                    person.person = Competitor {
+                        birth_year: member.birth_year.or(person.person.birth_year),
+                        ..member.clone()
+                    };
+                    person.member = Some(true);
+                }
+                None => person.member = Some(false),
+            }
+        }
+
+        if all_members {
+            for member in self.members.iter() {
+                if !persons.iter().any(|person| person.person.id == member.id) {
+                    persons.push(Person { person: member.clone(), billable: vec![], member: Some(true), totals: Totals::default() });
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use crate::iof::Document;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+
+// Where the data comes from: Eventor, or IOF XML files on disk. Requests are given as Eventor API
+// URLs and query parameters, and other sources answer them as Eventor would.
+pub trait Source {
+    // Also returns a key identifying the response by its content, so that the exact data can be
+    // found again later, or told apart from a newer version of it. A cached response older than
+    // `modified` is fetched again. An error tells what could not be read, e.g. which file.
+    fn request_modified_since(
+        &self,
+        url: &str,
+        parameters: &[(&str, &str)],
+        modified: Option<SystemTime>,
+    ) -> Result<(Document, String), String>;
+}
+
+impl<T: Source + ?Sized> Source for &T {
+    fn request_modified_since(
+        &self,
+        url: &str,
+        parameters: &[(&str, &str)],
+        modified: Option<SystemTime>,
+    ) -> Result<(Document, String), String> {
+        (**self).request_modified_since(url, parameters, modified)
+    }
+}
+
+// The SHA-256 of a response, in hex. Keys built from it stay valid when a cached or local file is
+// later replaced by a newer version.
+pub fn content_hash(text: &str) -> String {
+    hex(&Sha256::digest(text.as_bytes()))
+}
+
+// The same hash of a file's content, read a piece at a time.
+pub fn file_hash(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hex(&hasher.finalize()))
+}
+
+fn hex(hash: &[u8]) -> String {
+    hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,123 @@
+use crate::billing::{BillableEvent, ExtraKind};
+use crate::iof::CompetitorStatus;
+use serde::{Deserialize, Serialize};
+use std::ops::AddAssign;
+
+// How much of each kind of fee the member pays, in percent, with the club paying the rest.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SharePolicy {
+    #[serde(default = "all")]
+    normal: f64,
+    #[serde(default = "all")]
+    late: f64,
+    #[serde(default = "all")]
+    dns: f64,
+    #[serde(default = "all")]
+    card_rental: f64,
+    #[serde(default = "all")]
+    services: f64,
+}
+
+fn all() -> f64 {
+    100f64
+}
+
+// Sums of the fees billed, by kind. Fees for races not started are counted as DNS fees instead of
+// normal and late fees. The shares are only set when the club has a share policy. Lines already
+// paid are included in the sums, and what the member paid for them is in `paid`.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct Totals {
+    pub normal_fees: f64,
+    pub late_fees: f64,
+    pub dns_fees: f64,
+    pub card_rental: f64,
+    pub services: f64,
+    pub total: f64,
+    pub member_share: Option<f64>,
+    pub club_share: Option<f64>,
+    pub paid: f64,
+}
+
+impl AddAssign for Totals {
+    fn add_assign(&mut self, other: Totals) {
+        let add = |a: Option<f64>, b: Option<f64>| match (a, b) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0f64) + b.unwrap_or(0f64)),
+        };
+        self.normal_fees += other.normal_fees;
+        self.late_fees += other.late_fees;
+        self.dns_fees += other.dns_fees;
+        self.card_rental += other.card_rental;
+        self.services += other.services;
+        self.total += other.total;
+        self.member_share = add(self.member_share, other.member_share);
+        self.club_share = add(self.club_share, other.club_share);
+        self.paid += other.paid;
+    }
+}
+
+impl SharePolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        let shares = [
+            ("normal", self.normal),
+            ("late", self.late),
+            ("dns", self.dns),
+            ("card_rental", self.card_rental),
+            ("services", self.services),
+        ];
+        match shares.iter().find(|(_, share)| !(0f64..=100f64).contains(share)) {
+            Some((name, share)) => Err(format!("The member's share of {} fees is {}, but must be between 0 and 100 percent.", name, share)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Totals {
+    pub fn of(lines: &[BillableEvent], policy: Option<&SharePolicy>) -> Totals {
+        let mut totals = Totals::sum(lines.iter(), policy);
+        let paid = Totals::sum(lines.iter().filter(|line| line.paid), policy);
+        totals.paid = paid.member_share.unwrap_or(paid.total);
+        totals
+    }
+
+    // What the member is still to pay.
+    pub fn due(&self) -> f64 {
+        self.member_share.unwrap_or(self.total) - self.paid
+    }
+
+    fn sum<'a>(lines: impl Iterator<Item = &'a BillableEvent>, policy: Option<&SharePolicy>) -> Totals {
+        let mut totals = Totals::default();
+        for line in lines {
+            let mut fees = vec![(line.status, line.normal_fee, line.late_fee)];
+            fees.extend(line.stages.iter().map(|stage| (stage.status, stage.normal_fee, stage.late_fee)));
+            for (status, normal, late) in fees {
+                if status == CompetitorStatus::DidNotStart {
+                    totals.dns_fees += normal + late;
+                } else {
+                    totals.normal_fees += normal;
+                    totals.late_fees += late;
+                }
+            }
+            for extra in line.extras.iter() {
+                match extra.kind {
+                    ExtraKind::CardRental => totals.card_rental += extra.amount,
+                    ExtraKind::Service => totals.services += extra.amount,
+                }
+            }
+        }
+        totals.total = totals.normal_fees + totals.late_fees + totals.dns_fees + totals.card_rental + totals.services;
+
+        if let Some(policy) = policy {
+            let member_share = (totals.normal_fees * policy.normal
+                + totals.late_fees * policy.late
+                + totals.dns_fees * policy.dns
+                + totals.card_rental * policy.card_rental
+                + totals.services * policy.services)
+                / 100f64;
+            totals.member_share = Some(member_share);
+            totals.club_share = Some(totals.total - member_share);
+        }
+        totals
+    }
+}